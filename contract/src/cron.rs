@@ -0,0 +1,404 @@
+//! Calendar schedules for tasks.
+//!
+//! A standard five-field cron expression (`minute hour day-of-month month
+//! day-of-week`, all in UTC) is compiled into a [`CronSchedule`] of bitmasks,
+//! which is what tasks store on-chain. [`next_after`] walks the calendar
+//! forward from a timestamp to the next matching minute.
+
+use soroban_sdk::{contracttype, String};
+
+use crate::Error;
+
+/// Longest cron expression accepted by [`parse`], in bytes.
+pub const MAX_CRON_LEN: u32 = 128;
+
+/// Upper bound on calendar steps taken by [`next_after`]. Generous enough to
+/// reach the next 29 February across a skipped leap year.
+const MAX_SEARCH_STEPS: u32 = 4_000;
+
+const ALL_MINUTES: u64 = (1 << 60) - 1;
+const ALL_HOURS: u32 = (1 << 24) - 1;
+const ALL_DAYS_OF_MONTH: u32 = ((1 << 31) - 1) << 1;
+const ALL_MONTHS: u32 = ((1 << 12) - 1) << 1;
+const ALL_DAYS_OF_WEEK: u32 = (1 << 7) - 1;
+
+/// A compiled cron expression. Bit `n` of each mask is set when value `n`
+/// matches.
+///
+/// As in Vixie cron, when both `days_of_month` and `days_of_week` are
+/// restricted a day matches if *either* does; when one of them is
+/// unrestricted (all bits set) both must match.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct CronSchedule {
+    /// Bits 0–59.
+    pub minutes: u64,
+    /// Bits 0–23.
+    pub hours: u32,
+    /// Bits 1–31.
+    pub days_of_month: u32,
+    /// Bits 1–12.
+    pub months: u32,
+    /// Bits 0–6, Sunday is 0.
+    pub days_of_week: u32,
+}
+
+/// Compiles a cron expression.
+///
+/// Each field accepts `*`, a value, a range `a-b`, a step `*/n`, `a/n` or
+/// `a-b/n`, or a comma separated list of those. Day-of-week accepts 0–7 with
+/// both 0 and 7 meaning Sunday. Names (`MON`, `JAN`) are not supported.
+pub fn parse(expr: &String) -> Result<CronSchedule, Error> {
+    let len = expr.len();
+    if len == 0 || len > MAX_CRON_LEN {
+        return Err(Error::InvalidSchedule);
+    }
+    let mut buf = [0u8; MAX_CRON_LEN as usize];
+    let bytes = &mut buf[..len as usize];
+    expr.copy_into_slice(bytes);
+
+    let mut fields = bytes
+        .split(|b| b.is_ascii_whitespace())
+        .filter(|field| !field.is_empty());
+    let mut next_field = |min, max| match fields.next() {
+        Some(field) => parse_field(field, min, max),
+        None => Err(Error::InvalidSchedule),
+    };
+
+    let minutes = next_field(0, 59)?;
+    let hours = next_field(0, 23)?;
+    let days_of_month = next_field(1, 31)?;
+    let months = next_field(1, 12)?;
+    let mut days_of_week = next_field(0, 7)?;
+    if fields.next().is_some() {
+        return Err(Error::InvalidSchedule);
+    }
+
+    // Fold 7 onto Sunday.
+    if days_of_week & (1 << 7) != 0 {
+        days_of_week = (days_of_week & !(1 << 7)) | 1;
+    }
+
+    let schedule = CronSchedule {
+        minutes,
+        hours: hours as u32,
+        days_of_month: days_of_month as u32,
+        months: months as u32,
+        days_of_week: days_of_week as u32,
+    };
+    validate(&schedule)?;
+    Ok(schedule)
+}
+
+/// Checks that every mask is non-empty and in range, and that the schedule
+/// can fire at all (e.g. rejects `0 0 30 2 *`).
+pub fn validate(schedule: &CronSchedule) -> Result<(), Error> {
+    let in_range = schedule.minutes != 0
+        && schedule.minutes & !ALL_MINUTES == 0
+        && schedule.hours != 0
+        && schedule.hours & !ALL_HOURS == 0
+        && schedule.days_of_month != 0
+        && schedule.days_of_month & !ALL_DAYS_OF_MONTH == 0
+        && schedule.months != 0
+        && schedule.months & !ALL_MONTHS == 0
+        && schedule.days_of_week != 0
+        && schedule.days_of_week & !ALL_DAYS_OF_WEEK == 0;
+    if !in_range {
+        return Err(Error::InvalidSchedule);
+    }
+
+    // With day-of-week in play every month has a matching day; otherwise some
+    // selected month must be long enough for some selected day.
+    if schedule.days_of_week != ALL_DAYS_OF_WEEK && schedule.days_of_month != ALL_DAYS_OF_MONTH {
+        return Ok(());
+    }
+    let first_day = schedule.days_of_month.trailing_zeros();
+    let satisfiable = (1..=12)
+        .filter(|month| schedule.months & (1 << month) != 0)
+        .any(|month| first_day <= max_days_in_month(month));
+    if satisfiable {
+        Ok(())
+    } else {
+        Err(Error::InvalidSchedule)
+    }
+}
+
+/// Returns the first matching minute strictly after `timestamp`, as a Unix
+/// timestamp, or `None` if there is none within the search bound.
+pub fn next_after(schedule: &CronSchedule, timestamp: u64) -> Option<u64> {
+    let start = timestamp / 60 + 1;
+    let mut day = (start / 1_440) as i64;
+    let mut minute_of_day = (start % 1_440) as u32;
+
+    for _ in 0..MAX_SEARCH_STEPS {
+        let (year, month, day_of_month) = civil_from_days(day);
+        if schedule.months & (1 << month) == 0 {
+            // Jump to the first day of the next month.
+            let (year, month) = if month == 12 {
+                (year + 1, 1)
+            } else {
+                (year, month + 1)
+            };
+            day = days_from_civil(year, month, 1);
+            minute_of_day = 0;
+            continue;
+        }
+
+        if day_matches(schedule, day_of_month, day) {
+            if let Some(minute) = first_minute_from(schedule, minute_of_day) {
+                return Some((day as u64 * 1_440 + minute as u64) * 60);
+            }
+        }
+        day += 1;
+        minute_of_day = 0;
+    }
+    None
+}
+
+fn parse_field(field: &[u8], min: u32, max: u32) -> Result<u64, Error> {
+    let mut mask = 0u64;
+    for item in field.split(|b| *b == b',') {
+        let mut parts = item.splitn(2, |b| *b == b'/');
+        let base = parts.next().unwrap_or_default();
+        let step = match parts.next() {
+            Some(step) => parse_number(step)?,
+            None => 1,
+        };
+        if step == 0 {
+            return Err(Error::InvalidSchedule);
+        }
+
+        let (low, high) = if base == b"*" {
+            (min, max)
+        } else if let Some(dash) = base.iter().position(|b| *b == b'-') {
+            (
+                parse_number(&base[..dash])?,
+                parse_number(&base[dash + 1..])?,
+            )
+        } else {
+            let value = parse_number(base)?;
+            // `a/n` runs from `a` to the end of the range.
+            (value, if step > 1 { max } else { value })
+        };
+        if low < min || high > max || low > high {
+            return Err(Error::InvalidSchedule);
+        }
+
+        let mut value = low;
+        while value <= high {
+            mask |= 1 << value;
+            value += step;
+        }
+    }
+    Ok(mask)
+}
+
+fn parse_number(digits: &[u8]) -> Result<u32, Error> {
+    if digits.is_empty() || digits.len() > 3 {
+        return Err(Error::InvalidSchedule);
+    }
+    let mut value = 0u32;
+    for digit in digits {
+        if !digit.is_ascii_digit() {
+            return Err(Error::InvalidSchedule);
+        }
+        value = value * 10 + (digit - b'0') as u32;
+    }
+    Ok(value)
+}
+
+fn day_matches(schedule: &CronSchedule, day_of_month: u32, days: i64) -> bool {
+    // 1970-01-01 was a Thursday.
+    let day_of_week = (days + 4).rem_euclid(7) as u32;
+    let dom_hit = schedule.days_of_month & (1 << day_of_month) != 0;
+    let dow_hit = schedule.days_of_week & (1 << day_of_week) != 0;
+    if schedule.days_of_month == ALL_DAYS_OF_MONTH || schedule.days_of_week == ALL_DAYS_OF_WEEK {
+        dom_hit && dow_hit
+    } else {
+        dom_hit || dow_hit
+    }
+}
+
+/// First matching minute of the day at or after `minute_of_day`.
+fn first_minute_from(schedule: &CronSchedule, minute_of_day: u32) -> Option<u32> {
+    for hour in minute_of_day / 60..24 {
+        if schedule.hours & (1 << hour) == 0 {
+            continue;
+        }
+        let from = if hour == minute_of_day / 60 {
+            minute_of_day % 60
+        } else {
+            0
+        };
+        let remaining = schedule.minutes >> from;
+        if remaining != 0 {
+            return Some(hour * 60 + from + remaining.trailing_zeros());
+        }
+    }
+    None
+}
+
+fn max_days_in_month(month: u32) -> u32 {
+    match month {
+        2 => 29,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+/// Days since 1970-01-01 for a proleptic Gregorian date.
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let month = month as i64;
+    let day_of_year =
+        (153 * (if month > 2 { month - 3 } else { month + 9 }) + 2) / 5 + day as i64 - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+/// Inverse of [`days_from_civil`]: `(year, month, day)`.
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days - era * 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use soroban_sdk::Env;
+
+    fn compile(env: &Env, expr: &str) -> Result<CronSchedule, Error> {
+        parse(&String::from_str(env, expr))
+    }
+
+    /// 2024-01-01 00:00:00 UTC, a Monday.
+    const JAN_1_2024: u64 = 1_704_067_200;
+    const DAY: u64 = 86_400;
+
+    #[test]
+    fn test_civil_round_trip() {
+        for days in [-1, 0, 59, 365, 11_016, 19_723, 47_482] {
+            let (y, m, d) = civil_from_days(days);
+            assert_eq!(days_from_civil(y, m, d), days);
+        }
+        assert_eq!(civil_from_days(0), (1970, 1, 1));
+        assert_eq!(civil_from_days((JAN_1_2024 / DAY) as i64), (2024, 1, 1));
+        assert_eq!(days_from_civil(2000, 3, 1), 11_017);
+    }
+
+    #[test]
+    fn test_parse_fields() {
+        let env = Env::default();
+        let every_minute = compile(&env, "* * * * *").unwrap();
+        assert_eq!(every_minute.minutes, ALL_MINUTES);
+        assert_eq!(every_minute.days_of_week, ALL_DAYS_OF_WEEK);
+
+        let s = compile(&env, "0,30 */6 1-3 1-12/3 7").unwrap();
+        assert_eq!(s.minutes, 1 | 1 << 30);
+        assert_eq!(s.hours, 1 | 1 << 6 | 1 << 12 | 1 << 18);
+        assert_eq!(s.days_of_month, 1 << 1 | 1 << 2 | 1 << 3);
+        assert_eq!(s.months, 1 << 1 | 1 << 4 | 1 << 7 | 1 << 10);
+        assert_eq!(s.days_of_week, 1, "7 folds onto Sunday");
+
+        let offset = compile(&env, "5/20  0   *  * *").unwrap();
+        assert_eq!(offset.minutes, 1 << 5 | 1 << 25 | 1 << 45);
+    }
+
+    #[test]
+    fn test_parse_rejects_malformed() {
+        let env = Env::default();
+        for expr in [
+            "",
+            "* * * *",
+            "* * * * * *",
+            "60 * * * *",
+            "* 24 * * *",
+            "* * 0 * *",
+            "* * * 13 *",
+            "* * * * 8",
+            "5-1 * * * *",
+            "*/0 * * * *",
+            "a * * * *",
+            "1,,2 * * * *",
+            "- * * * *",
+            "0 0 30 2 *",
+        ] {
+            assert_eq!(compile(&env, expr), Err(Error::InvalidSchedule), "{expr}");
+        }
+    }
+
+    #[test]
+    fn test_next_after_weekly() {
+        let env = Env::default();
+        let monday_midnight = compile(&env, "0 0 * * 1").unwrap();
+
+        // Strictly after: a run exactly at the slot moves to the next week.
+        assert_eq!(
+            next_after(&monday_midnight, JAN_1_2024),
+            Some(JAN_1_2024 + 7 * DAY)
+        );
+        assert_eq!(
+            next_after(&monday_midnight, JAN_1_2024 - 1),
+            Some(JAN_1_2024)
+        );
+        assert_eq!(
+            next_after(&monday_midnight, JAN_1_2024 + 3 * DAY + 5),
+            Some(JAN_1_2024 + 7 * DAY)
+        );
+    }
+
+    #[test]
+    fn test_next_after_monthly_and_leap_day() {
+        let env = Env::default();
+        let first_of_month = compile(&env, "0 0 1 * *").unwrap();
+        // 2024-02-01
+        assert_eq!(
+            next_after(&first_of_month, JAN_1_2024),
+            Some(JAN_1_2024 + 31 * DAY)
+        );
+
+        let leap_day = compile(&env, "30 12 29 2 *").unwrap();
+        // 2024-02-29 12:30, then 2028-02-29 12:30.
+        let feb_29_2024 = JAN_1_2024 + 59 * DAY + 12 * 3_600 + 30 * 60;
+        assert_eq!(next_after(&leap_day, JAN_1_2024), Some(feb_29_2024));
+        let feb_29_2028 = (days_from_civil(2028, 2, 29) as u64) * DAY + 12 * 3_600 + 30 * 60;
+        assert_eq!(next_after(&leap_day, feb_29_2024), Some(feb_29_2028));
+    }
+
+    #[test]
+    fn test_next_after_within_day() {
+        let env = Env::default();
+        let s = compile(&env, "15,45 9-10 * * *").unwrap();
+        let nine_twenty = JAN_1_2024 + 9 * 3_600 + 20 * 60;
+        assert_eq!(next_after(&s, nine_twenty), Some(nine_twenty + 25 * 60));
+        let ten_fifty = JAN_1_2024 + 10 * 3_600 + 50 * 60;
+        assert_eq!(
+            next_after(&s, ten_fifty),
+            Some(JAN_1_2024 + DAY + 9 * 3_600 + 15 * 60)
+        );
+    }
+
+    /// Restricting both day fields matches either one.
+    #[test]
+    fn test_day_of_month_or_day_of_week() {
+        let env = Env::default();
+        // The 15th or any Sunday. 2024-01-07 is the first Sunday.
+        let s = compile(&env, "0 0 15 * 0").unwrap();
+        assert_eq!(next_after(&s, JAN_1_2024), Some(JAN_1_2024 + 6 * DAY));
+        assert_eq!(
+            next_after(&s, JAN_1_2024 + 13 * DAY),
+            Some(JAN_1_2024 + 14 * DAY)
+        );
+    }
+}
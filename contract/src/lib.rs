@@ -1,16 +1,282 @@
 #![no_std]
 use soroban_sdk::{
-    contract, contracterror, contractimpl, contracttype, panic_with_error, Address, Env, IntoVal,
-    Symbol, Val, Vec,
+    contract, contracterror, contractimpl, contracttype, panic_with_error, token, Address, Bytes,
+    Env, IntoVal, Map, String, Symbol, TryFromVal, Val, Vec,
 };
 
+mod cron;
+
+pub use cron::CronSchedule;
+
+/// Error codes returned by the contract.
+///
+/// Codes are part of the public interface: clients and keepers match on the
+/// numeric value returned by `try_*` calls, so existing codes must never be
+/// renumbered.
+///
+/// There is no `Unauthorized` code: creator, keeper and admin checks go
+/// through `require_auth`, so a missing or invalid signature comes back as
+/// the host's auth error rather than one of these codes.
 #[contracterror]
 #[derive(Copy, Clone, Debug, Eq, PartialEq, PartialOrd, Ord)]
 #[repr(u32)]
 pub enum Error {
+    /// The task's interval schedule is zero seconds.
     InvalidInterval = 1,
+    /// `initialize` was called more than once.
+    AlreadyInitialized = 2,
+    /// The contract has no fee token configured yet.
+    NotInitialized = 3,
+    /// No task is stored under the given ID.
+    TaskNotFound = 4,
+    /// A token amount is zero or negative where a positive value is required.
+    InvalidAmount = 5,
+    /// The task's gas balance cannot cover the requested amount or fee.
+    InsufficientBalance = 6,
+    /// The task is paused.
+    TaskPaused = 7,
+    /// The task is not paused.
+    TaskNotPaused = 8,
+    /// Reserved; `execute` reports this case as [`ExecutionOutcome::NotDue`].
+    NotDue = 9,
+    /// Reserved; `execute` reports this case as
+    /// [`ExecutionOutcome::ResolverDeclined`] or
+    /// [`ExecutionOutcome::ResolverFailed`].
+    ResolverRejected = 10,
+    /// The TTL threshold exceeds the extension target.
+    InvalidTtlConfig = 11,
+    /// The condition tree references a missing clause or node, has an empty
+    /// `And`/`Or`, a node without exactly one parent, or is too large.
+    InvalidCondition = 12,
+    /// The cron expression is malformed or can never fire, or a catch-up cap
+    /// is zero.
+    InvalidSchedule = 13,
+    /// The task has passed its `end_at` and no longer accepts changes.
+    TaskFinished = 14,
+    /// `end_at` is not after both `start_at` and the current time.
+    InvalidTimeWindow = 15,
+    /// `max_executions` is zero.
+    InvalidExecutionLimit = 16,
+    /// No keeper is registered under the given address.
+    KeeperNotRegistered = 17,
+    /// The task only accepts bonded keepers and the caller is not one.
+    KeeperNotBonded = 18,
+    /// The keeper is unbonding and cannot add stake or unbond again.
+    KeeperUnbonding = 19,
+    /// The keeper's stake is still bonded or within its unbonding delay.
+    StakeLocked = 20,
+    /// The stake is below the configured minimum.
+    InsufficientStake = 21,
+    /// The task has no assigned keeper.
+    TaskNotAssigned = 22,
+    /// The task is not overdue beyond the grace period, or it could not have
+    /// been run (declined by its resolver or underfunded).
+    NotMissed = 23,
+    /// The task is already assigned to another bonded keeper.
+    TaskAlreadyAssigned = 24,
+    /// The task has a keeper allowlist that does not include the caller.
+    KeeperNotAllowed = 25,
+    /// The keeper has no accrued rewards in the requested token.
+    NothingToClaim = 26,
+    /// The keeper's fee quote exceeds the task's `max_fee_per_run`.
+    FeeQuoteTooHigh = 27,
+    /// The task would call this contract or its fee token as a target,
+    /// resolver or condition clause.
+    ProtectedAddress = 28,
+    /// [`MAX_ACTIVE_KEEPERS`] keepers are already bonded.
+    KeeperRegistryFull = 29,
+}
+
+/// Maximum number of nodes in a task's condition tree.
+pub const MAX_CONDITION_NODES: u32 = 32;
+
+/// Number of keepers kept on the [`SoroTaskContract::top_keepers`]
+/// leaderboard.
+pub const MAX_RANKED_KEEPERS: u32 = 20;
+
+/// Maximum number of keepers bonded at the same time.
+pub const MAX_ACTIVE_KEEPERS: u32 = 64;
+
+/// Ledgers per day at the nominal 5 second close time.
+pub const DAY_IN_LEDGERS: u32 = 17_280;
+
+/// Storage TTL policy for task entries, set by the admin.
+///
+/// Whenever a task entry is touched with less than `threshold` ledgers of TTL
+/// left, it is extended to `extend_to` ledgers.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct TtlConfig {
+    pub threshold: u32,
+    pub extend_to: u32,
+    /// Paid from the task's gas balance to whoever calls
+    /// [`SoroTaskContract::bump_task`] when it actually extends the entry.
+    pub bump_fee: i128,
+}
+
+/// Keeper bonding and slashing policy, set by the admin.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct KeeperConfig {
+    /// Smallest total stake, in the fee token, a bonded keeper must hold.
+    /// Always positive.
+    pub min_stake: i128,
+    /// Seconds between [`SoroTaskContract::unregister_keeper`] and the stake
+    /// becoming withdrawable.
+    pub unbonding_period: u64,
+    /// Seconds an assigned task may stay overdue before
+    /// [`SoroTaskContract::report_missed`] can slash its keeper.
+    pub grace_period: u64,
+    /// Share of the keeper's stake slashed per missed task, in basis points.
+    pub slash_bps: u32,
+    /// Share of the slashed amount paid to the reporter, in basis points;
+    /// the rest is credited to the task's gas balance.
+    pub reporter_share_bps: u32,
+    /// Seconds after a task becomes due during which only its designated
+    /// keeper may execute it; see [`SoroTaskContract::designated_keeper`].
+    pub exclusive_window: u64,
+}
+
+/// Denominator of basis-point shares.
+pub const BPS_DENOMINATOR: u32 = 10_000;
+
+/// A registered keeper's bond.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct KeeperInfo {
+    /// Fee-token amount locked in the contract.
+    pub stake: i128,
+    /// Set once the keeper unregisters; the stake can be withdrawn from this
+    /// timestamp on. `None` while bonded.
+    pub unbonds_at: Option<u64>,
+}
+
+/// How a task's resolver is consulted before execution.
+#[contracttype]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ResolverMode {
+    /// `check_condition(args) -> bool`; the target receives the task's
+    /// static `args`. See [`ResolverInterface`].
+    Condition,
+    /// `check_payload(args) -> (bool, Vec<Val>)`; the target receives the
+    /// arguments computed by the resolver. See [`PayloadResolverInterface`].
+    Payload,
+}
+
+/// When a task becomes due.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Schedule {
+    /// Every `n` seconds after `last_run`.
+    Interval(u64),
+    /// At the next calendar slot after `last_run`. Build with
+    /// [`SoroTaskContract::parse_cron`].
+    Cron(CronSchedule),
+    /// At fixed slots `anchor + k * interval`, independent of when keepers
+    /// actually run the task. `last_run` records the last slot served rather
+    /// than the execution time, so late runs do not push the schedule back.
+    Anchored(AnchoredSchedule),
+}
+
+/// Slot-aligned schedule; see [`Schedule::Anchored`].
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct AnchoredSchedule {
+    /// Timestamp of the first slot.
+    pub anchor: u64,
+    pub interval: u64,
+    pub missed: MissedRunPolicy,
+}
+
+/// What an anchored task does when a keeper arrives after one or more whole
+/// slots have passed unserved.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum MissedRunPolicy {
+    /// Do not run late: drop the missed slots, including the current one,
+    /// and wait for the next future slot.
+    Skip,
+    /// Run once now and realign to the current slot.
+    RunOnce,
+    /// Invoke the target once per pending slot, oldest first, up to the given
+    /// number of runs per call; slots beyond the cap are dropped.
+    CatchUp(u32),
+}
+
+/// A single resolver call used as a leaf of a task's condition tree.
+///
+/// `resolver::function(args...)` must return `bool`; `args` are spread as
+/// positional arguments.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ResolverClause {
+    pub resolver: Address,
+    pub function: Symbol,
+    pub args: Vec<Val>,
+}
+
+/// A node of a task's condition tree.
+///
+/// The tree is stored flat: node 0 is the root and children are referenced
+/// by their index in the node list. Every node except the root has exactly
+/// one parent, with a lower index, which keeps the tree acyclic and limits
+/// evaluation to one visit per node.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum ConditionNode {
+    /// Evaluates the clause at this index of the task's `conditions`.
+    Clause(u32),
+    /// True when every child node is true; stops at the first false.
+    And(Vec<u32>),
+    /// True when any child node is true; stops at the first true.
+    Or(Vec<u32>),
+    /// Negates the child node.
+    Not(u32),
+}
+
+/// Lifecycle state of a registered task.
+#[contracttype]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum TaskStatus {
+    Active,
+    Paused,
+    /// The task reached its `end_at` or `max_executions`; it never runs
+    /// again and its remaining gas balance was refunded to the creator.
+    Finished,
+}
+
+/// Creator-supplied settings of a new task, passed to
+/// [`SoroTaskContract::register`].
+///
+/// Execution state (`last_run`, `execution_count`, `status`, `live_until`)
+/// is owned by the contract and only exists on the stored [`TaskConfig`].
+#[contracttype]
+#[derive(Clone, Debug)]
+pub struct TaskParams {
+    pub creator: Address,
+    pub target: Address,
+    pub function: Symbol,
+    pub args: Vec<Val>,
+    pub resolver: Option<Address>,
+    pub resolver_mode: ResolverMode,
+    pub resolver_function: Option<Symbol>,
+    pub resolver_args: Vec<Val>,
+    pub conditions: Vec<ResolverClause>,
+    pub condition_tree: Vec<ConditionNode>,
+    pub schedule: Schedule,
+    pub start_at: u64,
+    pub end_at: Option<u64>,
+    pub max_executions: Option<u32>,
+    pub bonded_only: bool,
+    pub allowed_keepers: Vec<Address>,
+    /// Initial deposit, escrowed from the creator.
+    pub gas_balance: i128,
+    pub fee: i128,
+    pub fee_policy: FeePolicy,
 }
 
+/// A registered task as stored by the contract. See [`TaskParams`] for the
+/// creator-supplied fields.
 #[contracttype]
 #[derive(Clone, Debug)]
 pub struct TaskConfig {
@@ -19,35 +285,317 @@ pub struct TaskConfig {
     pub function: Symbol,
     pub args: Vec<Val>,
     pub resolver: Option<Address>,
-    pub interval: u64,
+    /// Ignored when `resolver` is `None`.
+    pub resolver_mode: ResolverMode,
+    /// Resolver function to call instead of the mode's interface function.
+    /// When set, it is called with `resolver_args` as positional arguments;
+    /// when `None`, the interface function receives `args` as its single
+    /// argument.
+    pub resolver_function: Option<Symbol>,
+    pub resolver_args: Vec<Val>,
+    /// Leaves of the condition tree.
+    pub conditions: Vec<ResolverClause>,
+    /// Boolean operator tree over `conditions`, evaluated before `resolver`.
+    /// An empty tree imposes no condition.
+    pub condition_tree: Vec<ConditionNode>,
+    pub schedule: Schedule,
+    /// Set by the contract; 0 at registration, then the time (or, for
+    /// anchored schedules, the slot) of the last successful run.
     pub last_run: u64,
+    /// The task is not due before this timestamp; 0 imposes no start time.
+    pub start_at: u64,
+    /// The task does not run at or after this timestamp. The first
+    /// [`SoroTaskContract::execute`] call from then on marks it
+    /// [`TaskStatus::Finished`] and refunds the remaining gas balance.
+    pub end_at: Option<u64>,
+    /// Number of successful runs after which the task completes; `None` runs
+    /// until cancelled or expired. `Some(1)` makes a one-shot task.
+    pub max_executions: Option<u32>,
+    /// Set by the contract; successful target invocations so far.
+    pub execution_count: u32,
+    /// Only keepers with an active bond (see
+    /// [`SoroTaskContract::register_keeper`]) may execute the task.
+    pub bonded_only: bool,
+    /// Keepers permitted to execute or claim the task; empty allows any
+    /// keeper. Managed with [`SoroTaskContract::add_allowed_keeper`] and
+    /// [`SoroTaskContract::remove_allowed_keeper`].
+    pub allowed_keepers: Vec<Address>,
+    /// Set by the contract; the keeper that claimed the task with
+    /// [`SoroTaskContract::claim_task`] and is slashed if it misses a run.
+    pub assigned_keeper: Option<Address>,
+    /// Set by the contract; when `assigned_keeper` claimed the task.
+    pub assigned_at: u64,
     pub gas_balance: i128,
+    /// Reward paid to the keeper out of `gas_balance` for each successful
+    /// run, as adjusted by `fee_policy`.
+    pub fee: i128,
+    pub fee_policy: FeePolicy,
+    /// Set by the contract; always [`TaskStatus::Active`] at registration.
+    pub status: TaskStatus,
+    /// Set by the contract; ledger sequence the task entry is kept live until.
+    pub live_until: u32,
+}
+
+/// Result of a call to [`SoroTaskContract::execute`].
+///
+/// Only `Executed` changes task state or pays the keeper; the other outcomes
+/// are no-ops the keeper can tell apart without re-reading the task.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum ExecutionOutcome {
+    /// The target was invoked and the keeper paid.
+    Executed,
+    /// The task is not due yet; carries the timestamp at which it will be.
+    NotDue(u64),
+    /// The resolver returned `false`.
+    ResolverDeclined,
+    /// The resolver call failed (trapped or returned the wrong type).
+    ResolverFailed,
+    /// The target call failed. Only reported by
+    /// [`SoroTaskContract::execute_batch`]; `execute` reverts instead.
+    TargetFailed,
+    /// The task reached its `end_at` and was finished; its remaining gas
+    /// balance was refunded to the creator.
+    Finished,
+    /// The task is due but inside another keeper's exclusive window; carries
+    /// the timestamp at which it opens to every keeper.
+    Reserved(u64),
+    /// Missed slots of a [`MissedRunPolicy::Skip`] task were dropped without
+    /// running; carries the timestamp of the next slot.
+    SlotsSkipped(u64),
+    /// The task could not be run; carries the [`Error`] code. Only reported
+    /// by [`SoroTaskContract::execute_batch`]; `execute` fails instead.
+    Rejected(u32),
+}
+
+/// Result of a call to [`SoroTaskContract::execute_batch`].
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct BatchResult {
+    /// One outcome per requested task ID, in request order.
+    pub outcomes: Vec<ExecutionOutcome>,
+    /// Sum of the fees paid to the keeper across the batch.
+    pub total_reward: i128,
+}
+
+/// Selective changes applied by [`SoroTaskContract::update_task`].
+///
+/// `None` leaves a field unchanged. Because `resolver` is itself optional, a
+/// resolver is removed with `clear_resolver`, which takes precedence over
+/// `resolver`.
+#[contracttype]
+#[derive(Clone, Debug)]
+pub struct TaskPatch {
+    pub target: Option<Address>,
+    pub function: Option<Symbol>,
+    pub args: Option<Vec<Val>>,
+    pub resolver: Option<Address>,
+    pub clear_resolver: bool,
+    /// The resolver settings below are applied together with `resolver` and
+    /// ignored when `resolver` is `None`.
+    pub resolver_mode: ResolverMode,
+    pub resolver_function: Option<Symbol>,
+    pub resolver_args: Vec<Val>,
+    /// Replace the condition tree; the result is validated as in `register`.
+    pub conditions: Option<Vec<ResolverClause>>,
+    pub condition_tree: Option<Vec<ConditionNode>>,
+    /// Switch to an interval schedule. Cron schedules are set with
+    /// [`SoroTaskContract::set_schedule`].
+    pub interval: Option<u64>,
+    pub bonded_only: Option<bool>,
+}
+
+/// How the keeper reward for one run of a task is computed from its `fee`.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum FeePolicy {
+    /// `fee` per run.
+    Fixed,
+    /// `fee` plus the given amount per argument passed to the target, after
+    /// any payload resolver has supplied them.
+    PerArg(i128),
+    /// The keeper quotes the fee with [`SoroTaskContract::execute_quoted`],
+    /// capped by the given `max_fee_per_run`; without a quote, `fee`.
+    Quoted(i128),
+}
+
+/// How a keeper receives execution rewards and bump fees.
+#[contracttype]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum PayoutMode {
+    /// Transferred to the keeper on every call.
+    Transfer,
+    /// Credited to the keeper's ledger in the contract and withdrawn with
+    /// [`SoroTaskContract::claim_rewards`].
+    Accrue,
+}
+
+/// Performance counters of a keeper, updated by every execution attempt
+/// that reaches the target.
+#[contracttype]
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct KeeperStats {
+    /// Successful target invocations.
+    pub executions: u64,
+    /// Fees earned from those invocations.
+    pub rewards: i128,
+    /// Target calls that failed in [`SoroTaskContract::execute_batch`].
+    pub failures: u64,
+    /// Executions that happened after the task's due time.
+    pub late_executions: u64,
+    /// Sum over executions of the seconds between due time and execution.
+    pub total_delay: u64,
+}
+
+/// An entry of [`SoroTaskContract::top_keepers`].
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct KeeperRanking {
+    pub keeper: Address,
+    pub stats: KeeperStats,
+}
+
+/// A task found due by [`SoroTaskContract::monitor`].
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct DueTask {
+    pub task_id: u64,
+    /// Timestamp at which the task became due.
+    pub next_run_at: u64,
 }
 
 #[contracttype]
 pub enum DataKey {
     Task(u64),
     Counter,
+    Admin,
+    Token,
+    TtlConfig,
+    KeeperConfig,
+    Keeper(Address),
+    /// Addresses of the currently bonded keepers, in registration order.
+    Keepers,
+    KeeperStats(Address),
+    /// The [`MAX_RANKED_KEEPERS`] best keepers with their stats, best first.
+    RankedKeepers,
+    PayoutMode(Address),
+    /// Rewards accrued to a keeper, by keeper and token.
+    Accrued(Address, Address),
+}
+
+/// Task layout written by the first release of the contract, before gas was
+/// escrowed in a fee token. Upgraded in place by
+/// [`SoroTaskContract::migrate_tasks`].
+#[contracttype]
+#[derive(Clone, Debug)]
+pub struct LegacyTaskConfig {
+    pub creator: Address,
+    pub target: Address,
+    pub function: Symbol,
+    pub args: Vec<Val>,
+    pub resolver: Option<Address>,
+    pub interval: u64,
+    pub last_run: u64,
+    pub gas_balance: i128,
 }
 
+/// Interface of a [`ResolverMode::Condition`] resolver.
 pub trait ResolverInterface {
     fn check_condition(env: Env, args: Vec<Val>) -> bool;
 }
 
+/// Interface of a [`ResolverMode::Payload`] resolver.
+///
+/// Returns whether the task should run and, if so, the arguments to call the
+/// target with in place of the task's static `args`.
+pub trait PayloadResolverInterface {
+    fn check_payload(env: Env, args: Vec<Val>) -> (bool, Vec<Val>);
+}
+
 #[contract]
 pub struct SoroTaskContract;
 
 #[contractimpl]
 impl SoroTaskContract {
+    /// One-time setup of the marketplace.
+    ///
+    /// `token` is the SEP-41 token in which task gas balances are escrowed
+    /// and keepers are paid.
+    pub fn initialize(env: Env, admin: Address, token: Address) {
+        if env.storage().instance().has(&DataKey::Admin) {
+            panic_with_error!(&env, Error::AlreadyInitialized);
+        }
+        admin.require_auth();
+
+        env.storage().instance().set(&DataKey::Admin, &admin);
+        env.storage().instance().set(&DataKey::Token, &token);
+    }
+
+    /// Replaces the storage TTL policy. Admin only.
+    pub fn set_ttl_config(env: Env, config: TtlConfig) {
+        admin(&env).require_auth();
+
+        if config.threshold > config.extend_to {
+            panic_with_error!(&env, Error::InvalidTtlConfig);
+        }
+        if config.bump_fee < 0 {
+            panic_with_error!(&env, Error::InvalidAmount);
+        }
+        env.storage().instance().set(&DataKey::TtlConfig, &config);
+    }
+
+    /// Returns the storage TTL policy, falling back to the defaults of a
+    /// 30 day threshold, 120 day extension and no bump fee.
+    pub fn get_ttl_config(env: Env) -> TtlConfig {
+        ttl_config(&env)
+    }
+
+    /// Returns the fee token used for gas balances.
+    pub fn get_token(env: Env) -> Address {
+        fee_token(&env)
+    }
+
     /// Registers a new task in the marketplace.
     /// Returns the unique sequential ID of the registered task.
-    pub fn register(env: Env, config: TaskConfig) -> u64 {
+    ///
+    /// `params.gas_balance` is transferred from the creator into the contract
+    /// and held in escrow for the task. The task starts with `last_run` 0, so
+    /// it is due as soon as its schedule and `start_at` allow.
+    pub fn register(env: Env, params: TaskParams) -> u64 {
         // Ensure the creator has authorized the registration
-        config.creator.require_auth();
+        params.creator.require_auth();
+
+        let mut config = new_task(params);
+
+        // Validate the task schedule
+        if let Err(error) = validate_schedule(&config.schedule) {
+            panic_with_error!(&env, error);
+        }
+        if let Some(end_at) = config.end_at {
+            if end_at <= config.start_at || end_at <= env.ledger().timestamp() {
+                panic_with_error!(&env, Error::InvalidTimeWindow);
+            }
+        }
+        if config.gas_balance < 0 || !valid_fee(config.fee, &config.fee_policy) {
+            panic_with_error!(&env, Error::InvalidAmount);
+        }
+        if config.max_executions == Some(0) {
+            panic_with_error!(&env, Error::InvalidExecutionLimit);
+        }
+        if let Err(error) = validate_conditions(&config) {
+            panic_with_error!(&env, error);
+        }
+        if let Err(error) = validate_callees(&env, &config) {
+            panic_with_error!(&env, error);
+        }
 
-        // Validate the task interval
-        if config.interval == 0 {
-            panic_with_error!(&env, Error::InvalidInterval);
+        // Escrow the initial gas deposit
+        if config.gas_balance > 0 {
+            token::Client::new(&env, &fee_token(&env)).transfer(
+                &config.creator,
+                &env.current_contract_address(),
+                &config.gas_balance,
+            );
         }
 
         // Generate a unique sequential ID
@@ -59,10 +607,13 @@ impl SoroTaskContract {
         counter += 1;
         env.storage().persistent().set(&DataKey::Counter, &counter);
 
-        // Store the task configuration
+        // Store the task configuration and keep it (and the counter) live
+        let ttl = ttl_config(&env);
+        store_task(&env, counter, &mut config, &ttl);
         env.storage()
             .persistent()
-            .set(&DataKey::Task(counter), &config);
+            .extend_ttl(&DataKey::Counter, ttl.threshold, ttl.extend_to);
+        extend_instance(&env, &ttl);
 
         // Emit TaskRegistered event
         env.events().publish(
@@ -78,473 +629,5994 @@ impl SoroTaskContract {
         env.storage().persistent().get(&DataKey::Task(task_id))
     }
 
-    pub fn monitor(_env: Env) {
-        // TODO: Implement task monitoring logic
+    /// Rewrites tasks stored in the [`LegacyTaskConfig`] layout as
+    /// [`TaskConfig`]. Admin only.
+    ///
+    /// Scans the same ID page as [`monitor`](Self::monitor) and returns the
+    /// number of tasks upgraded; entries already in the current layout are
+    /// left alone, so the call can be repeated safely. Legacy balances were
+    /// never escrowed, so migrated tasks start with a zero gas balance and
+    /// fee; `last_run` is kept. A legacy task that calls a protected address
+    /// (see [`Error::ProtectedAddress`]) is stored as
+    /// [`TaskStatus::Finished`] so it can never run.
+    pub fn migrate_tasks(env: Env, start_id: u64, limit: u32) -> u32 {
+        admin(&env).require_auth();
+
+        let ttl = ttl_config(&env);
+        let count = Self::task_count(env.clone());
+        let first = start_id.max(1);
+        let last = first.saturating_add(limit as u64).min(count + 1);
+        let legacy_key = Symbol::new(&env, "interval");
+
+        let mut migrated = 0;
+        for task_id in first..last {
+            let stored: Option<Map<Symbol, Val>> =
+                env.storage().persistent().get(&DataKey::Task(task_id));
+            let Some(stored) = stored.filter(|m| m.contains_key(legacy_key.clone())) else {
+                continue;
+            };
+            let legacy = LegacyTaskConfig::try_from_val(&env, &stored.to_val())
+                .unwrap_or_else(|_| panic_with_error!(&env, Error::TaskNotFound));
+
+            let mut config = new_task(TaskParams {
+                creator: legacy.creator,
+                target: legacy.target,
+                function: legacy.function,
+                args: legacy.args,
+                resolver: legacy.resolver,
+                resolver_mode: ResolverMode::Condition,
+                resolver_function: None,
+                resolver_args: Vec::new(&env),
+                conditions: Vec::new(&env),
+                condition_tree: Vec::new(&env),
+                schedule: Schedule::Interval(legacy.interval.max(1)),
+                start_at: 0,
+                end_at: None,
+                max_executions: None,
+                bonded_only: false,
+                allowed_keepers: Vec::new(&env),
+                gas_balance: 0,
+                fee: 0,
+                fee_policy: FeePolicy::Fixed,
+            });
+            config.last_run = legacy.last_run;
+            if validate_callees(&env, &config).is_err() {
+                config.status = TaskStatus::Finished;
+            }
+            store_task(&env, task_id, &mut config, &ttl);
+
+            env.events()
+                .publish((Symbol::new(&env, "TaskMigrated"), task_id), config.creator);
+            migrated += 1;
+        }
+        extend_instance(&env, &ttl);
+        migrated
     }
 
-    /// Executes a registered task identified by `task_id`.
+    /// Extends the storage TTL of a task so it is not archived between runs.
     ///
-    /// # Flow
-    /// 1. Load the [`TaskConfig`] from persistent storage (panics if absent).
-    /// 2. If a `resolver` address is set, call `check_condition(args) -> bool`
-    ///    on it via [`try_invoke_contract`] so that a faulty resolver never
-    ///    permanently blocks execution — a failed call is treated as `false`.
-    /// 3. When the condition is met (or there is no resolver), fire the
-    ///    cross-contract call to `target::function(args)` using
-    ///    [`invoke_contract`].
-    /// 4. Only on a **successful** invocation persist the updated `last_run`
-    ///    timestamp.
-    ///
-    /// # Safety & Atomicity
-    /// Soroban transactions are fully atomic. If the target contract panics the
-    /// entire transaction reverts, so `SoroTask` state is never left in an
-    /// inconsistent half-updated form. `last_run` is written **after** the
-    /// cross-contract call returns, guaranteeing it only reflects completed
-    /// executions.
-    pub fn execute(env: Env, task_id: u64) {
-        let task_key = DataKey::Task(task_id);
-        let mut config: TaskConfig = env
-            .storage()
-            .persistent()
-            .get(&task_key)
-            .expect("Task not found");
+    /// Anyone may call this. When the entry is within the policy threshold
+    /// it is extended and `caller` is reimbursed the configured `bump_fee`
+    /// from the task's gas balance, if the balance covers it. Returns whether
+    /// the entry was extended; a bump that is not needed pays nothing.
+    pub fn bump_task(env: Env, caller: Address, task_id: u64) -> bool {
+        caller.require_auth();
 
-        if env.ledger().timestamp() < config.last_run + config.interval {
-            return;
+        let mut config = load_task(&env, task_id);
+        let ttl = ttl_config(&env);
+        extend_instance(&env, &ttl);
+
+        if !needs_extension(&env, &config, &ttl) {
+            return false;
         }
 
-        // ── Resolver gate ────────────────────────────────────────────────────
-        // When a resolver is present we use try_invoke_contract so that an
-        // error inside the resolver (panic / wrong return type) degrades
-        // gracefully to "skip this run" rather than aborting the whole tx.
-        //
-        // The resolver's interface is:  check_condition(args: Vec<Val>) -> bool
-        // Its single explicit argument is the task's args vector, so we must
-        // pack config.args into a one-element outer Vec<Val> — otherwise the
-        // host would unpack config.args as individual positional arguments,
-        // causing an argument-count mismatch.
-        let should_execute = match config.resolver {
-            Some(ref resolver_address) => {
-                let mut resolver_call_args = Vec::<Val>::new(&env);
-                resolver_call_args.push_back(config.args.clone().into_val(&env));
-                matches!(
-                    env.try_invoke_contract::<bool, soroban_sdk::Error>(
-                        resolver_address,
-                        &Symbol::new(&env, "check_condition"),
-                        resolver_call_args,
-                    ),
-                    Ok(Ok(true))
-                )
-            }
-            None => true,
+        let fee = if ttl.bump_fee > 0 && config.gas_balance >= ttl.bump_fee {
+            ttl.bump_fee
+        } else {
+            0
         };
+        config.gas_balance -= fee;
+        store_task(&env, task_id, &mut config, &ttl);
 
-        if should_execute {
-            // ── Cross-contract call ──────────────────────────────────────────
-            // `args` is Vec<Val> as stored in TaskConfig — passed directly.
-            // The return value is discarded; callers can read target state
-            // independently if needed.
-            env.invoke_contract::<Val>(&config.target, &config.function, config.args.clone());
-
-            // ── State update ────────────────────────────────────────────────
-            // Reached only when invoke_contract returned without panic.
-            // Record the ledger timestamp of this successful execution.
-            config.last_run = env.ledger().timestamp();
-            env.storage().persistent().set(&task_key, &config);
+        if fee > 0 {
+            reward_keeper(&env, &caller, fee);
         }
+        env.events().publish(
+            (Symbol::new(&env, "TaskBumped"), task_id),
+            (caller, config.live_until, fee),
+        );
+        true
     }
-}
 
-// ============================================================================
-// Tests
-// ============================================================================
+    /// Tops up the escrowed gas balance of a task.
+    ///
+    /// The creator must authorize; `amount` is transferred from the creator
+    /// into the contract.
+    pub fn deposit_gas(env: Env, task_id: u64, amount: i128) {
+        if amount <= 0 {
+            panic_with_error!(&env, Error::InvalidAmount);
+        }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use soroban_sdk::{
-        contract, contractimpl,
-        testutils::{Address as _, Events, Ledger as _},
-        vec, Env, FromVal, IntoVal,
-    };
+        let task_key = DataKey::Task(task_id);
+        let mut config = load_unfinished_task(&env, task_id);
+        config.creator.require_auth();
 
-    // ── Mock Contracts ───────────────────────────────────────────────────────
+        token::Client::new(&env, &fee_token(&env)).transfer(
+            &config.creator,
+            &env.current_contract_address(),
+            &amount,
+        );
 
-    #[contract]
-    pub struct DummyContract;
+        config.gas_balance += amount;
+        // An underfunded task could not be run; the grace period restarts.
+        config.assigned_at = env.ledger().timestamp();
+        env.storage().persistent().set(&task_key, &config);
 
-    #[contractimpl]
-    impl DummyContract {
-        pub fn hello(_env: Env) {}
+        env.events().publish(
+            (Symbol::new(&env, "GasDeposited"), task_id),
+            (amount, config.gas_balance),
+        );
     }
 
-    /// Minimal target contract with two callable functions.
-    #[contract]
-    pub struct MockTarget;
-
-    #[contractimpl]
-    impl MockTarget {
-        /// Zero-argument smoke-test function.
-        pub fn ping(_env: Env) -> bool {
-            true
+    /// Withdraws part of a task's escrowed gas balance back to the creator.
+    pub fn withdraw_gas(env: Env, task_id: u64, amount: i128) {
+        if amount <= 0 {
+            panic_with_error!(&env, Error::InvalidAmount);
         }
 
-        /// Two-argument function — verifies args are forwarded correctly.
-        pub fn add(_env: Env, a: i64, b: i64) -> i64 {
-            a + b
+        let task_key = DataKey::Task(task_id);
+        let mut config = load_task(&env, task_id);
+        config.creator.require_auth();
+
+        if amount > config.gas_balance {
+            panic_with_error!(&env, Error::InsufficientBalance);
         }
-    }
 
-    // ── Resolver contracts (separate sub-modules) ───────────────────────
+        config.gas_balance -= amount;
+        env.storage().persistent().set(&task_key, &config);
 
-    /// Resolver that always approves execution.
-    mod resolver_true {
-        use soroban_sdk::{contract, contractimpl, Env, Val, Vec};
+        token::Client::new(&env, &fee_token(&env)).transfer(
+            &env.current_contract_address(),
+            &config.creator,
+            &amount,
+        );
 
-        #[contract]
-        pub struct MockResolverTrue;
+        env.events().publish(
+            (Symbol::new(&env, "GasWithdrawn"), task_id),
+            (amount, config.gas_balance),
+        );
+    }
 
-        #[contractimpl]
-        impl MockResolverTrue {
-            pub fn check_condition(_env: Env, _args: Vec<Val>) -> bool {
-                true
-            }
+    /// Cancels a task, refunding its remaining gas balance to the creator.
+    ///
+    /// The task entry is removed; its ID is never reused and subsequent
+    /// calls referencing it fail with [`Error::TaskNotFound`].
+    pub fn cancel_task(env: Env, task_id: u64) {
+        let config = load_task(&env, task_id);
+        config.creator.require_auth();
+
+        env.storage().persistent().remove(&DataKey::Task(task_id));
+
+        if config.gas_balance > 0 {
+            token::Client::new(&env, &fee_token(&env)).transfer(
+                &env.current_contract_address(),
+                &config.creator,
+                &config.gas_balance,
+            );
         }
-    }
 
-    /// Resolver that always denies execution.
-    mod resolver_false {
-        use soroban_sdk::{contract, contractimpl, Env, Val, Vec};
+        env.events().publish(
+            (Symbol::new(&env, "TaskCancelled"), task_id),
+            (config.creator, config.gas_balance),
+        );
+    }
 
-        #[contract]
-        pub struct MockResolverFalse;
+    /// Replaces selected fields of a task in place, keeping its ID, balance
+    /// and history.
+    ///
+    /// Applies the same validation as [`register`](Self::register) and emits
+    /// `TaskUpdated` with the names of the fields present in the patch.
+    pub fn update_task(env: Env, task_id: u64, patch: TaskPatch) {
+        let mut config = load_unfinished_task(&env, task_id);
+        config.creator.require_auth();
 
-        #[contractimpl]
-        impl MockResolverFalse {
-            pub fn check_condition(_env: Env, _args: Vec<Val>) -> bool {
-                false
+        let mut changed = Vec::<Symbol>::new(&env);
+        if let Some(target) = patch.target {
+            config.target = target;
+            changed.push_back(Symbol::new(&env, "target"));
+        }
+        if let Some(function) = patch.function {
+            config.function = function;
+            changed.push_back(Symbol::new(&env, "function"));
+        }
+        if let Some(args) = patch.args {
+            config.args = args;
+            changed.push_back(Symbol::new(&env, "args"));
+        }
+        if patch.clear_resolver {
+            config.resolver = None;
+            changed.push_back(Symbol::new(&env, "resolver"));
+        } else if let Some(resolver) = patch.resolver {
+            config.resolver = Some(resolver);
+            config.resolver_mode = patch.resolver_mode;
+            config.resolver_function = patch.resolver_function;
+            config.resolver_args = patch.resolver_args;
+            changed.push_back(Symbol::new(&env, "resolver"));
+        }
+        if let Some(conditions) = patch.conditions {
+            config.conditions = conditions;
+            changed.push_back(Symbol::new(&env, "conditions"));
+        }
+        if let Some(condition_tree) = patch.condition_tree {
+            config.condition_tree = condition_tree;
+            changed.push_back(Symbol::new(&env, "condition_tree"));
+        }
+        if let Err(error) = validate_conditions(&config) {
+            panic_with_error!(&env, error);
+        }
+        if let Err(error) = validate_callees(&env, &config) {
+            panic_with_error!(&env, error);
+        }
+        if let Some(interval) = patch.interval {
+            if interval == 0 {
+                panic_with_error!(&env, Error::InvalidInterval);
             }
+            config.schedule = Schedule::Interval(interval);
+            changed.push_back(Symbol::new(&env, "interval"));
         }
-    }
+        if let Some(bonded_only) = patch.bonded_only {
+            config.bonded_only = bonded_only;
+            changed.push_back(Symbol::new(&env, "bonded_only"));
+        }
+        // The assigned keeper only answers for the terms it claimed.
+        config.assigned_keeper = None;
 
-    // ── Helpers ──────────────────────────────────────────────────────────────
+        env.storage()
+            .persistent()
+            .set(&DataKey::Task(task_id), &config);
 
-    fn setup() -> (Env, Address) {
-        let env = Env::default();
-        env.mock_all_auths();
-        let id = env.register_contract(None, SoroTaskContract);
-        (env, id)
+        env.events()
+            .publish((Symbol::new(&env, "TaskUpdated"), task_id), changed);
     }
 
-    fn base_config(env: &Env, target: Address) -> TaskConfig {
-        TaskConfig {
-            creator: Address::generate(env),
-            target,
-            function: Symbol::new(env, "ping"),
-            args: Vec::new(env),
-            resolver: None,
-            interval: 3_600,
-            last_run: 0,
-            gas_balance: 1_000,
+    /// Replaces a task's schedule, e.g. to switch it to a cron schedule.
+    ///
+    /// Validated as in [`register`](Self::register); emits `TaskUpdated`.
+    pub fn set_schedule(env: Env, task_id: u64, schedule: Schedule) {
+        let mut config = load_unfinished_task(&env, task_id);
+        config.creator.require_auth();
+
+        if let Err(error) = validate_schedule(&schedule) {
+            panic_with_error!(&env, error);
         }
-    }
+        config.schedule = schedule;
+        config.assigned_keeper = None;
+        env.storage()
+            .persistent()
+            .set(&DataKey::Task(task_id), &config);
 
-    fn set_timestamp(env: &Env, ts: u64) {
-        env.ledger().with_mut(|l| l.timestamp = ts);
+        let mut changed = Vec::<Symbol>::new(&env);
+        changed.push_back(Symbol::new(&env, "schedule"));
+        env.events()
+            .publish((Symbol::new(&env, "TaskUpdated"), task_id), changed);
     }
 
-    // ── Tests ─────────────────────────────────────────────────────────────────
+    /// Adds `keeper` to the task's allowlist. Creator only.
+    ///
+    /// Adding the first keeper restricts the task to the list; adding a
+    /// keeper that is already listed has no effect. A claim by a keeper the
+    /// list no longer admits is dropped.
+    pub fn add_allowed_keeper(env: Env, task_id: u64, keeper: Address) {
+        let mut config = load_unfinished_task(&env, task_id);
+        config.creator.require_auth();
 
-    /// Registering a task stores it; get_task retrieves identical data.
-    #[test]
-    fn test_register_and_get_task() {
-        let (env, id) = setup();
+        if config.allowed_keepers.contains(&keeper) {
+            return;
+        }
+        config.allowed_keepers.push_back(keeper.clone());
+        drop_disallowed_assignment(&mut config);
+        env.storage()
+            .persistent()
+            .set(&DataKey::Task(task_id), &config);
+
+        env.events()
+            .publish((Symbol::new(&env, "KeeperAllowed"), task_id), keeper);
+    }
+
+    /// Removes `keeper` from the task's allowlist. Creator only.
+    ///
+    /// Removing the last keeper opens the task to every keeper again.
+    pub fn remove_allowed_keeper(env: Env, task_id: u64, keeper: Address) {
+        let mut config = load_unfinished_task(&env, task_id);
+        config.creator.require_auth();
+
+        let Some(index) = config.allowed_keepers.first_index_of(&keeper) else {
+            return;
+        };
+        config.allowed_keepers.remove(index);
+        drop_disallowed_assignment(&mut config);
+        env.storage()
+            .persistent()
+            .set(&DataKey::Task(task_id), &config);
+
+        env.events()
+            .publish((Symbol::new(&env, "KeeperDisallowed"), task_id), keeper);
+    }
+
+    /// Replaces a task's fee and fee policy. Creator only; emits
+    /// `TaskUpdated`.
+    pub fn set_fee_policy(env: Env, task_id: u64, fee: i128, policy: FeePolicy) {
+        let mut config = load_unfinished_task(&env, task_id);
+        config.creator.require_auth();
+
+        if !valid_fee(fee, &policy) {
+            panic_with_error!(&env, Error::InvalidAmount);
+        }
+        config.fee = fee;
+        config.fee_policy = policy;
+        config.assigned_keeper = None;
+        env.storage()
+            .persistent()
+            .set(&DataKey::Task(task_id), &config);
+
+        let mut changed = Vec::<Symbol>::new(&env);
+        changed.push_back(Symbol::new(&env, "fee"));
+        changed.push_back(Symbol::new(&env, "fee_policy"));
+        env.events()
+            .publish((Symbol::new(&env, "TaskUpdated"), task_id), changed);
+    }
+
+    /// Compiles a five-field cron expression (UTC) into the compact form
+    /// stored in [`Schedule::Cron`].
+    ///
+    /// Fails with [`Error::InvalidSchedule`] for malformed expressions and
+    /// for expressions that can never fire.
+    pub fn parse_cron(env: Env, expr: String) -> CronSchedule {
+        cron::parse(&expr).unwrap_or_else(|error| panic_with_error!(&env, error))
+    }
+
+    /// Temporarily stops a task from being executed.
+    ///
+    /// The task keeps its ID, gas balance and `last_run`; it is skipped by
+    /// [`monitor`](Self::monitor) and rejected by [`execute`](Self::execute)
+    /// until resumed.
+    pub fn pause_task(env: Env, task_id: u64) {
+        set_status(&env, task_id, TaskStatus::Paused);
+    }
+
+    /// Re-activates a paused task.
+    pub fn resume_task(env: Env, task_id: u64) {
+        set_status(&env, task_id, TaskStatus::Active);
+    }
+
+    /// Returns the number of task IDs issued so far.
+    pub fn task_count(env: Env) -> u64 {
+        env.storage()
+            .persistent()
+            .get(&DataKey::Counter)
+            .unwrap_or(0)
+    }
+
+    /// Scans one page of the task ID space for tasks that are due.
+    ///
+    /// Examines at most `limit` IDs starting at `start_id` (IDs begin at 1)
+    /// and returns the active ones whose next run time has passed, in ID
+    /// order. Tasks past their `end_at` are not reported.
+    /// Keepers sweep the full space by advancing `start_id` by `limit` until
+    /// it exceeds [`task_count`](Self::task_count).
+    pub fn monitor(env: Env, start_id: u64, limit: u32) -> Vec<DueTask> {
+        let mut due = Vec::new(&env);
+        let count = Self::task_count(env.clone());
+        let now = env.ledger().timestamp();
+
+        let first = start_id.max(1);
+        let last = first.saturating_add(limit as u64).min(count + 1);
+        for task_id in first..last {
+            let config: Option<TaskConfig> =
+                env.storage().persistent().get(&DataKey::Task(task_id));
+            let config = config.filter(|c| c.status == TaskStatus::Active && !is_expired(&env, c));
+            if let Some(config) = config {
+                let next_run_at = next_run_at(&config);
+                if next_run_at <= now {
+                    due.push_back(DueTask {
+                        task_id,
+                        next_run_at,
+                    });
+                }
+            }
+        }
+        due
+    }
+
+    /// Replaces the keeper bonding policy. Admin only.
+    pub fn set_keeper_config(env: Env, config: KeeperConfig) {
+        admin(&env).require_auth();
+
+        if config.min_stake <= 0
+            || config.slash_bps > BPS_DENOMINATOR
+            || config.reporter_share_bps > BPS_DENOMINATOR
+        {
+            panic_with_error!(&env, Error::InvalidAmount);
+        }
+        env.storage()
+            .instance()
+            .set(&DataKey::KeeperConfig, &config);
+    }
+
+    /// Returns the keeper policy, falling back to the defaults of a minimum
+    /// stake of 1, a 7 day unbonding period, a 1 hour grace period, a 10% slash
+    /// split evenly between reporter and task and a 60 second exclusive
+    /// window.
+    pub fn get_keeper_config(env: Env) -> KeeperConfig {
+        keeper_config(&env)
+    }
+
+    /// Bonds `stake` of the fee token for `keeper`, registering it or adding
+    /// to an existing bond.
+    ///
+    /// The resulting total must reach the configured `min_stake`. Fails with
+    /// [`Error::KeeperUnbonding`] while a previous bond is unbonding and with
+    /// [`Error::KeeperRegistryFull`] when a new keeper would exceed
+    /// [`MAX_ACTIVE_KEEPERS`]. Only bonded keepers hold a place: a full
+    /// registry first drops listed keepers that no longer meet `min_stake`
+    /// or are unbonding.
+    pub fn register_keeper(env: Env, keeper: Address, stake: i128) {
+        keeper.require_auth();
+
+        if stake <= 0 {
+            panic_with_error!(&env, Error::InvalidAmount);
+        }
+        let key = DataKey::Keeper(keeper.clone());
+        let existing: Option<KeeperInfo> = env.storage().persistent().get(&key);
+        let mut info = match existing {
+            Some(info) if info.unbonds_at.is_some() => {
+                panic_with_error!(&env, Error::KeeperUnbonding)
+            }
+            Some(info) => info,
+            None => KeeperInfo {
+                stake: 0,
+                unbonds_at: None,
+            },
+        };
+        if info.stake + stake < keeper_config(&env).min_stake {
+            panic_with_error!(&env, Error::InsufficientStake);
+        }
+
+        token::Client::new(&env, &fee_token(&env)).transfer(
+            &keeper,
+            &env.current_contract_address(),
+            &stake,
+        );
+
+        // A keeper slashed down to zero stake is still listed.
+        let mut keepers = Self::active_keepers(env.clone());
+        if !keepers.contains(&keeper) {
+            if keepers.len() >= MAX_ACTIVE_KEEPERS {
+                let mut bonded = Vec::new(&env);
+                for listed in keepers.iter() {
+                    if is_bonded(&env, &listed) {
+                        bonded.push_back(listed);
+                    }
+                }
+                keepers = bonded;
+            }
+            if keepers.len() >= MAX_ACTIVE_KEEPERS {
+                panic_with_error!(&env, Error::KeeperRegistryFull);
+            }
+            keepers.push_back(keeper.clone());
+            store_keepers(&env, &keepers);
+        }
+        info.stake += stake;
+        store_keeper(&env, &keeper, &info);
+
+        env.events()
+            .publish((Symbol::new(&env, "KeeperRegistered"), keeper), info.stake);
+    }
+
+    /// Starts unbonding `keeper`'s stake.
+    ///
+    /// The keeper stops counting as bonded immediately; its stake can be
+    /// withdrawn with [`withdraw_stake`](Self::withdraw_stake) once the
+    /// unbonding period has passed.
+    pub fn unregister_keeper(env: Env, keeper: Address) {
+        keeper.require_auth();
+
+        let mut info = load_keeper(&env, &keeper);
+        if info.unbonds_at.is_some() {
+            panic_with_error!(&env, Error::KeeperUnbonding);
+        }
+        let unbonds_at = env
+            .ledger()
+            .timestamp()
+            .saturating_add(keeper_config(&env).unbonding_period);
+        info.unbonds_at = Some(unbonds_at);
+        store_keeper(&env, &keeper, &info);
+
+        let mut keepers = Self::active_keepers(env.clone());
+        if let Some(index) = keepers.first_index_of(&keeper) {
+            keepers.remove(index);
+        }
+        store_keepers(&env, &keepers);
+
+        env.events()
+            .publish((Symbol::new(&env, "KeeperUnbonding"), keeper), unbonds_at);
+    }
+
+    /// Returns an unbonded keeper's stake and removes its registration.
+    ///
+    /// Fails with [`Error::StakeLocked`] while the keeper is still bonded or
+    /// its unbonding period has not passed.
+    pub fn withdraw_stake(env: Env, keeper: Address) {
+        keeper.require_auth();
+
+        let info = load_keeper(&env, &keeper);
+        match info.unbonds_at {
+            Some(unbonds_at) if env.ledger().timestamp() >= unbonds_at => {}
+            _ => panic_with_error!(&env, Error::StakeLocked),
+        }
+
+        env.storage()
+            .persistent()
+            .remove(&DataKey::Keeper(keeper.clone()));
+        if info.stake > 0 {
+            pay_keeper(&env, &keeper, info.stake);
+        }
+
+        env.events()
+            .publish((Symbol::new(&env, "StakeWithdrawn"), keeper), info.stake);
+    }
+
+    /// Assigns a task to a bonded keeper, making it accountable for the
+    /// task's runs through [`report_missed`](Self::report_missed).
+    ///
+    /// Fails with [`Error::TaskAlreadyAssigned`] if another keeper that is
+    /// still bonded holds the task. The assignment is dropped when the
+    /// creator changes the task through [`update_task`](Self::update_task),
+    /// [`set_schedule`](Self::set_schedule) or
+    /// [`set_fee_policy`](Self::set_fee_policy); pausing, resuming and
+    /// deposits restart the grace period.
+    pub fn claim_task(env: Env, keeper: Address, task_id: u64) {
+        keeper.require_auth();
+
+        let mut config = load_unfinished_task(&env, task_id);
+        if !is_allowed(&config, &keeper) {
+            panic_with_error!(&env, Error::KeeperNotAllowed);
+        }
+        if !is_bonded(&env, &keeper) {
+            panic_with_error!(&env, Error::KeeperNotBonded);
+        }
+        if let Some(assigned) = &config.assigned_keeper {
+            if *assigned != keeper && is_bonded(&env, assigned) {
+                panic_with_error!(&env, Error::TaskAlreadyAssigned);
+            }
+        }
+
+        config.assigned_keeper = Some(keeper.clone());
+        config.assigned_at = env.ledger().timestamp();
+        store_task(&env, task_id, &mut config, &ttl_config(&env));
+
+        env.events()
+            .publish((Symbol::new(&env, "TaskClaimed"), task_id), keeper);
+    }
+
+    /// Gives up a task previously claimed by `keeper`.
+    pub fn release_task(env: Env, keeper: Address, task_id: u64) {
+        keeper.require_auth();
+
+        let mut config = load_task(&env, task_id);
+        if config.assigned_keeper.as_ref() != Some(&keeper) {
+            panic_with_error!(&env, Error::TaskNotAssigned);
+        }
+        config.assigned_keeper = None;
+        store_task(&env, task_id, &mut config, &ttl_config(&env));
+
+        env.events()
+            .publish((Symbol::new(&env, "TaskReleased"), task_id), keeper);
+    }
+
+    /// Slashes the keeper assigned to a task that has stayed due for longer
+    /// than the grace period.
+    ///
+    /// Anyone may report. The task must be active and runnable by the
+    /// assigned keeper: its resolver is consulted, and a declined or failing
+    /// resolver, like an underfunded task, a keeper outside the task's
+    /// allowlist or an unbonded keeper on a `bonded_only` task, fails with
+    /// [`Error::NotMissed`]. `slash_bps` of the keeper's
+    /// stake is taken; `reporter_share_bps` of it is paid to `reporter` and
+    /// the rest credited to the task's gas balance. The keeper is unassigned,
+    /// so one miss is slashed once. A claimer whose
+    /// [`execute_batch`](Self::execute_batch) attempt fails in the target
+    /// restarts its grace period. Returns the slashed amount.
+    pub fn report_missed(env: Env, reporter: Address, task_id: u64) -> i128 {
+        reporter.require_auth();
+
+        let mut config = load_unfinished_task(&env, task_id);
+        let keeper = config
+            .assigned_keeper
+            .clone()
+            .unwrap_or_else(|| panic_with_error!(&env, Error::TaskNotAssigned));
+        let policy = keeper_config(&env);
+
+        let overdue_since = next_run_at(&config).max(config.assigned_at);
+        let missed = config.status == TaskStatus::Active
+            && is_allowed(&config, &keeper)
+            && (!config.bonded_only || is_bonded(&env, &keeper))
+            && !is_expired(&env, &config)
+            && env.ledger().timestamp() >= overdue_since.saturating_add(policy.grace_period)
+            && resolve(&env, &config).is_ok_and(|call_args| {
+                run_fee(&config, &call_args, None).is_ok_and(|fee| config.gas_balance >= fee)
+            });
+        if !missed {
+            panic_with_error!(&env, Error::NotMissed);
+        }
+
+        let mut info = load_keeper(&env, &keeper);
+        let slashed = info.stake * policy.slash_bps as i128 / BPS_DENOMINATOR as i128;
+        let reporter_share = slashed * policy.reporter_share_bps as i128 / BPS_DENOMINATOR as i128;
+        info.stake -= slashed;
+        store_keeper(&env, &keeper, &info);
+
+        config.assigned_keeper = None;
+        config.gas_balance += slashed - reporter_share;
+        store_task(&env, task_id, &mut config, &ttl_config(&env));
+
+        if reporter_share > 0 {
+            pay_keeper(&env, &reporter, reporter_share);
+        }
+        env.events().publish(
+            (Symbol::new(&env, "KeeperSlashed"), task_id),
+            (keeper, slashed, reporter),
+        );
+        slashed
+    }
+
+    /// Returns the keeper entitled to the task's current or upcoming run
+    /// during the exclusive window, or `None` when no keeper is bonded.
+    ///
+    /// A task claimed by a bonded keeper belongs to that keeper. Otherwise
+    /// the designated keeper is picked from the
+    /// [`active_keepers`](Self::active_keepers) still holding at least
+    /// `min_stake`, at the index given by the
+    /// first 8 bytes (big-endian) of `sha256(task_id ++ due_at)`, modulo the
+    /// number of keepers, so each run rotates the keeper deterministically.
+    pub fn designated_keeper(env: Env, task_id: u64) -> Option<Address> {
+        let config = load_task(&env, task_id);
+        designated_keeper(&env, task_id, &config, next_run_at(&config))
+    }
+
+    /// Chooses how `keeper` is paid for executions and bumps from now on.
+    ///
+    /// Switching back to [`PayoutMode::Transfer`] leaves already accrued
+    /// rewards claimable.
+    pub fn set_payout_mode(env: Env, keeper: Address, mode: PayoutMode) {
+        keeper.require_auth();
+
+        let key = DataKey::PayoutMode(keeper);
+        let ttl = ttl_config(&env);
+        env.storage().persistent().set(&key, &mode);
+        env.storage()
+            .persistent()
+            .extend_ttl(&key, ttl.threshold, ttl.extend_to);
+    }
+
+    /// Returns how `keeper` is paid; [`PayoutMode::Transfer`] by default.
+    pub fn get_payout_mode(env: Env, keeper: Address) -> PayoutMode {
+        payout_mode(&env, &keeper)
+    }
+
+    /// Returns the rewards accrued to `keeper` in `token` and not yet
+    /// claimed.
+    pub fn get_accrued_rewards(env: Env, keeper: Address, token: Address) -> i128 {
+        env.storage()
+            .persistent()
+            .get(&DataKey::Accrued(keeper, token))
+            .unwrap_or(0)
+    }
+
+    /// Transfers all of `keeper`'s accrued rewards in `token` to it in one
+    /// transfer and returns the amount.
+    ///
+    /// Fails with [`Error::NothingToClaim`] when nothing has accrued.
+    pub fn claim_rewards(env: Env, keeper: Address, token: Address) -> i128 {
+        keeper.require_auth();
+
+        let key = DataKey::Accrued(keeper.clone(), token.clone());
+        let amount: i128 = env.storage().persistent().get(&key).unwrap_or(0);
+        if amount <= 0 {
+            panic_with_error!(&env, Error::NothingToClaim);
+        }
+        env.storage().persistent().remove(&key);
+
+        token::Client::new(&env, &token).transfer(
+            &env.current_contract_address(),
+            &keeper,
+            &amount,
+        );
+
+        env.events().publish(
+            (Symbol::new(&env, "RewardsClaimed"), keeper),
+            (token, amount),
+        );
+        amount
+    }
+
+    /// Returns a keeper's performance counters; all zero for a keeper that
+    /// has never executed a task.
+    pub fn get_keeper_stats(env: Env, keeper: Address) -> KeeperStats {
+        env.storage()
+            .persistent()
+            .get(&DataKey::KeeperStats(keeper))
+            .unwrap_or_default()
+    }
+
+    /// Returns up to `limit` keepers ranked by executions, then by fewest
+    /// failures, then by fewest late executions.
+    ///
+    /// The leaderboard is maintained as stats change and holds at most
+    /// [`MAX_RANKED_KEEPERS`] entries, so it is approximate: a keeper is only
+    /// placed when its own stats change. A keeper left off a full board stays
+    /// off, even once a listed keeper falls below it, until it records its
+    /// next outcome.
+    pub fn top_keepers(env: Env, limit: u32) -> Vec<KeeperRanking> {
+        let mut top = leaderboard(&env);
+        while top.len() > limit {
+            top.pop_back();
+        }
+        top
+    }
+
+    /// Returns a keeper's bond, or `None` if it is not registered.
+    pub fn get_keeper(env: Env, keeper: Address) -> Option<KeeperInfo> {
+        env.storage().persistent().get(&DataKey::Keeper(keeper))
+    }
+
+    /// Returns the currently bonded keepers, in registration order.
+    pub fn active_keepers(env: Env) -> Vec<Address> {
+        env.storage()
+            .persistent()
+            .get(&DataKey::Keepers)
+            .unwrap_or(Vec::new(&env))
+    }
+
+    /// Executes a registered task identified by `task_id`.
+    ///
+    /// # Flow
+    /// 1. Load the [`TaskConfig`] from persistent storage, failing with
+    ///    [`Error::TaskNotFound`] if it was never registered or was cancelled.
+    /// 2. If the task's `end_at` has passed, finish it, refund its gas
+    ///    balance to the creator and return [`ExecutionOutcome::Finished`].
+    ///    If the schedule's next slot after `last_run` (and not before
+    ///    `start_at`) has not been reached, return
+    ///    [`ExecutionOutcome::NotDue`].
+    /// 3. If the task has a condition tree, evaluate it (see
+    ///    [`ConditionNode`]); a false tree returns
+    ///    [`ExecutionOutcome::ResolverDeclined`] and any failing clause
+    ///    returns [`ExecutionOutcome::ResolverFailed`]. Then, if a `resolver`
+    ///    address is set, call `check_condition(args) -> bool`
+    ///    (or `check_payload(args) -> (bool, Vec<Val>)` in
+    ///    [`ResolverMode::Payload`]) on it via [`try_invoke_contract`] so that
+    ///    a faulty resolver never aborts the call — a `false` answer returns
+    ///    [`ExecutionOutcome::ResolverDeclined`] and a failed call returns
+    ///    [`ExecutionOutcome::ResolverFailed`].
+    /// 4. When the condition is met (or there is no resolver), fire the
+    ///    cross-contract call to `target::function(args)` using
+    ///    [`invoke_contract`]. In payload mode the resolver-computed
+    ///    arguments replace `args`.
+    /// 5. Only on a **successful** invocation persist the updated `last_run`
+    ///    timestamp and `execution_count`, and pay `fee` from the task's
+    ///    `gas_balance` to `keeper` (or credit it, see [`PayoutMode`]). A task
+    ///    reaching `max_executions` is finished and its remaining balance
+    ///    refunded (`TaskCompleted`).
+    ///
+    /// While the task is within the exclusive window of another keeper (see
+    /// [`designated_keeper`](Self::designated_keeper)), returns
+    /// [`ExecutionOutcome::Reserved`] instead of running it.
+    ///
+    /// Every outcome emits an event topic'd with the outcome name and
+    /// `task_id`: `TaskExecuted`, `TaskNotDue`, `TaskReserved`,
+    /// `TaskFinished`, `ResolverDeclined` or `ResolverFailed`.
+    ///
+    /// # Errors
+    /// - [`Error::TaskNotFound`] — no task is stored under `task_id`.
+    /// - [`Error::TaskPaused`] — the task is paused.
+    /// - [`Error::TaskFinished`] — the task has already finished.
+    /// - [`Error::KeeperNotAllowed`] — `keeper` is not on the task's
+    ///   non-empty `allowed_keepers` list.
+    /// - [`Error::KeeperNotBonded`] — the task is `bonded_only` and `keeper`
+    ///   has no active bond.
+    /// - [`Error::InsufficientBalance`] — the gas balance cannot cover the
+    ///   run's fee under the task's [`FeePolicy`].
+    /// - [`Error::InvalidAmount`] — the run's fee under the task's
+    ///   [`FeePolicy`] overflows.
+    ///
+    /// # Safety & Atomicity
+    /// Soroban transactions are fully atomic. If the target contract panics the
+    /// entire transaction reverts, so `SoroTask` state is never left in an
+    /// inconsistent half-updated form. `last_run` is written **after** the
+    /// cross-contract call returns, guaranteeing it only reflects completed
+    /// executions.
+    pub fn execute(env: Env, keeper: Address, task_id: u64) -> ExecutionOutcome {
+        keeper.require_auth();
+        execute_single(&env, &keeper, task_id, None)
+    }
+
+    /// Executes a task like [`execute`](Self::execute), charging `quote` per
+    /// run instead of `fee` for a task with [`FeePolicy::Quoted`].
+    ///
+    /// Fails with [`Error::FeeQuoteTooHigh`] when `quote` exceeds the task's
+    /// `max_fee_per_run` and [`Error::InvalidAmount`] when it is negative.
+    /// The quote is ignored for other fee policies.
+    pub fn execute_quoted(
+        env: Env,
+        keeper: Address,
+        task_id: u64,
+        quote: i128,
+    ) -> ExecutionOutcome {
+        keeper.require_auth();
+        execute_single(&env, &keeper, task_id, Some(quote))
+    }
+
+    /// Executes several tasks in one transaction.
+    ///
+    /// Each task runs in isolation: the target is invoked with
+    /// [`try_invoke_contract`], so a failing target is reported as
+    /// [`ExecutionOutcome::TargetFailed`] and a task that cannot run (missing,
+    /// paused, underfunded) as [`ExecutionOutcome::Rejected`], without
+    /// reverting the other tasks. Fees from every executed task are paid to
+    /// `keeper` in a single transfer, or a single credit under
+    /// [`PayoutMode::Accrue`].
+    pub fn execute_batch(env: Env, keeper: Address, task_ids: Vec<u64>) -> BatchResult {
+        keeper.require_auth();
+
+        let mut outcomes = Vec::new(&env);
+        let mut total_reward: i128 = 0;
+        for task_id in task_ids.iter() {
+            let outcome = match run_task(&env, &keeper, task_id, true, None) {
+                Ok((outcome, reward)) => {
+                    if reward > 0 {
+                        total_reward += reward;
+                        env.events().publish(
+                            (Symbol::new(&env, "KeeperPaid"), task_id),
+                            (keeper.clone(), reward),
+                        );
+                    }
+                    outcome
+                }
+                Err(error) => ExecutionOutcome::Rejected(error as u32),
+            };
+            outcomes.push_back(outcome);
+        }
+
+        if total_reward > 0 {
+            reward_keeper(&env, &keeper, total_reward);
+        }
+
+        BatchResult {
+            outcomes,
+            total_reward,
+        }
+    }
+}
+
+/// Runs one task for [`SoroTaskContract::execute`] and pays the keeper,
+/// reverting on any error.
+fn execute_single(
+    env: &Env,
+    keeper: &Address,
+    task_id: u64,
+    quote: Option<i128>,
+) -> ExecutionOutcome {
+    match run_task(env, keeper, task_id, false, quote) {
+        Ok((outcome, reward)) => {
+            // ── Keeper payout ────────────────────────────────────────────────
+            if reward > 0 {
+                reward_keeper(env, keeper, reward);
+                env.events().publish(
+                    (Symbol::new(env, "KeeperPaid"), task_id),
+                    (keeper.clone(), reward),
+                );
+            }
+            outcome
+        }
+        Err(error) => panic_with_error!(env, error),
+    }
+}
+
+/// Runs one task on behalf of `keeper` and returns its outcome together with
+/// the fee debited from the task's gas balance. The fee is not transferred;
+/// the caller pays it out.
+///
+/// With `isolated` set the target is invoked via [`try_invoke_contract`] and
+/// a failure yields [`ExecutionOutcome::TargetFailed`]; otherwise a failing
+/// target reverts the whole transaction.
+fn run_task(
+    env: &Env,
+    keeper: &Address,
+    task_id: u64,
+    isolated: bool,
+    quote: Option<i128>,
+) -> Result<(ExecutionOutcome, i128), Error> {
+    let task_key = DataKey::Task(task_id);
+    let mut config: TaskConfig = env
+        .storage()
+        .persistent()
+        .get(&task_key)
+        .ok_or(Error::TaskNotFound)?;
+
+    if config.status == TaskStatus::Finished {
+        return Err(Error::TaskFinished);
+    }
+    if is_expired(env, &config) {
+        finish_task(env, task_id, &mut config, "TaskFinished");
+        return Ok((ExecutionOutcome::Finished, 0));
+    }
+    if config.status == TaskStatus::Paused {
+        return Err(Error::TaskPaused);
+    }
+    if !is_allowed(&config, keeper) {
+        return Err(Error::KeeperNotAllowed);
+    }
+    if config.bonded_only && !is_bonded(env, keeper) {
+        return Err(Error::KeeperNotBonded);
+    }
+
+    let now = env.ledger().timestamp();
+    let next_at = next_run_at(&config);
+    if now < next_at {
+        env.events()
+            .publish((Symbol::new(env, "TaskNotDue"), task_id), next_at);
+        return Ok((ExecutionOutcome::NotDue(next_at), 0));
+    }
+
+    // ── Exclusive window ─────────────────────────────────────────────────────
+    let open_at = next_at.saturating_add(keeper_config(env).exclusive_window);
+    if now < open_at {
+        let designated = designated_keeper(env, task_id, &config, next_at);
+        if designated.is_some_and(|designated| designated != *keeper) {
+            env.events()
+                .publish((Symbol::new(env, "TaskReserved"), task_id), open_at);
+            return Ok((ExecutionOutcome::Reserved(open_at), 0));
+        }
+    }
+
+    // ── Run planning ─────────────────────────────────────────────────────────
+    // Anchored schedules may owe several slots; everything else runs once.
+    let (pending, served_until) = pending_runs(&config, now);
+    let ttl = ttl_config(env);
+    if pending == 0 {
+        config.last_run = served_until;
+        store_task(env, task_id, &mut config, &ttl);
+        let next_at = next_run_at(&config);
+        env.events()
+            .publish((Symbol::new(env, "SlotsSkipped"), task_id), next_at);
+        return Ok((ExecutionOutcome::SlotsSkipped(next_at), 0));
+    }
+
+    if let (FeePolicy::Quoted(max_fee_per_run), Some(quote)) = (&config.fee_policy, quote) {
+        if quote < 0 {
+            return Err(Error::InvalidAmount);
+        }
+        if quote > *max_fee_per_run {
+            return Err(Error::FeeQuoteTooHigh);
+        }
+    }
+
+    // ── Resolver gate ────────────────────────────────────────────────────────
+    let call_args = match resolve(env, &config) {
+        Ok(call_args) => call_args,
+        Err(outcome) => {
+            let event = match outcome {
+                ExecutionOutcome::ResolverDeclined => "ResolverDeclined",
+                _ => "ResolverFailed",
+            };
+            env.events()
+                .publish((Symbol::new(env, event), task_id), config.resolver.clone());
+            return Ok((outcome, 0));
+        }
+    };
+
+    // ── Fee ──────────────────────────────────────────────────────────────────
+    // Priced once the target arguments are known; the fee covers every run.
+    let run_fee = run_fee(&config, &call_args, quote)?;
+    if config.gas_balance < run_fee {
+        return Err(Error::InsufficientBalance);
+    }
+    let affordable = if run_fee > 0 {
+        (config.gas_balance / run_fee).min(u32::MAX as i128) as u32
+    } else {
+        u32::MAX
+    };
+    let remaining = config
+        .max_executions
+        .map_or(u32::MAX, |max| max - config.execution_count);
+    let runs = pending.min(affordable).min(remaining);
+
+    // ── Cross-contract call ──────────────────────────────────────────────────
+    // `call_args` is Vec<Val> — passed directly. The return value is
+    // discarded; callers can read target state independently if needed.
+    // In isolated mode a failing call stops further catch-up runs; the runs
+    // that already succeeded are kept.
+    let mut completed = 0;
+    while completed < runs {
+        if isolated {
+            let result = env.try_invoke_contract::<Val, soroban_sdk::Error>(
+                &config.target,
+                &config.function,
+                call_args.clone(),
+            );
+            if !matches!(result, Ok(Ok(_))) {
+                break;
+            }
+        } else {
+            env.invoke_contract::<Val>(&config.target, &config.function, call_args.clone());
+        }
+        completed += 1;
+    }
+    if completed == 0 {
+        // The claimer showed up; a failing target is not its miss.
+        if config.assigned_keeper.as_ref() == Some(keeper) {
+            config.assigned_at = env.ledger().timestamp();
+            store_task(env, task_id, &mut config, &ttl);
+        }
+        record_stats(env, keeper, |stats| stats.failures += 1);
+        env.events().publish(
+            (Symbol::new(env, "TargetFailed"), task_id),
+            config.target.clone(),
+        );
+        return Ok((ExecutionOutcome::TargetFailed, 0));
+    }
+
+    // ── State update ─────────────────────────────────────────────────────────
+    // Reached only when the target call succeeded.
+    // Record the slot (or, for unanchored schedules, the ledger timestamp) of
+    // this successful execution and debit the keeper's reward from the
+    // escrowed balance.
+    let reward = run_fee * completed as i128;
+    config.last_run = served_until;
+    config.gas_balance -= reward;
+    config.execution_count += completed;
+    store_task(env, task_id, &mut config, &ttl);
+    extend_instance(env, &ttl);
+
+    env.events().publish(
+        (Symbol::new(env, "TaskExecuted"), task_id),
+        (keeper.clone(), config.last_run),
+    );
+
+    if config.max_executions == Some(config.execution_count) {
+        finish_task(env, task_id, &mut config, "TaskCompleted");
+    }
+
+    let delay = now - next_at;
+    let late = now > next_at;
+    record_stats(env, keeper, |stats| {
+        stats.executions += completed as u64;
+        stats.rewards += reward;
+        stats.total_delay += delay;
+        if late {
+            stats.late_executions += 1;
+        }
+    });
+
+    Ok((ExecutionOutcome::Executed, reward))
+}
+
+/// Rejects tasks that would call this contract or its fee token. The
+/// contract is the direct caller of every task call, so such a call could
+/// move escrowed funds without any other authorization.
+fn validate_callees(env: &Env, config: &TaskConfig) -> Result<(), Error> {
+    if is_protected(env, &config.target) {
+        return Err(Error::ProtectedAddress);
+    }
+    if let Some(resolver) = &config.resolver {
+        if is_protected(env, resolver) {
+            return Err(Error::ProtectedAddress);
+        }
+    }
+    if config
+        .conditions
+        .iter()
+        .any(|clause| is_protected(env, &clause.resolver))
+    {
+        return Err(Error::ProtectedAddress);
+    }
+    Ok(())
+}
+
+fn is_protected(env: &Env, address: &Address) -> bool {
+    *address == env.current_contract_address() || *address == fee_token(env)
+}
+
+/// The fee of one run calling the target with `call_args`, under the task's
+/// [`FeePolicy`] and the keeper's `quote`, if any.
+fn run_fee(config: &TaskConfig, call_args: &Vec<Val>, quote: Option<i128>) -> Result<i128, Error> {
+    match config.fee_policy {
+        FeePolicy::Fixed => Some(config.fee),
+        FeePolicy::PerArg(per_arg) => per_arg
+            .checked_mul(call_args.len() as i128)
+            .and_then(|args_fee| config.fee.checked_add(args_fee)),
+        FeePolicy::Quoted(_) => Some(quote.unwrap_or(config.fee)),
+    }
+    .filter(|run_fee| *run_fee >= 0)
+    .ok_or(Error::InvalidAmount)
+}
+
+/// Whether `fee` and `policy` are non-negative and the fee fits under a
+/// quoted policy's cap.
+fn valid_fee(fee: i128, policy: &FeePolicy) -> bool {
+    let within_policy = match *policy {
+        FeePolicy::Fixed => true,
+        FeePolicy::PerArg(per_arg) => per_arg >= 0,
+        FeePolicy::Quoted(max_fee_per_run) => fee <= max_fee_per_run,
+    };
+    fee >= 0 && within_policy
+}
+
+/// Applies `update` to `keeper`'s stats and moves the keeper to its place
+/// on the leaderboard, which drops whoever falls past
+/// [`MAX_RANKED_KEEPERS`].
+fn record_stats(env: &Env, keeper: &Address, update: impl FnOnce(&mut KeeperStats)) {
+    let key = DataKey::KeeperStats(keeper.clone());
+    let ttl = ttl_config(env);
+    let mut stats: KeeperStats = env.storage().persistent().get(&key).unwrap_or_default();
+    update(&mut stats);
+    env.storage().persistent().set(&key, &stats);
+    env.storage()
+        .persistent()
+        .extend_ttl(&key, ttl.threshold, ttl.extend_to);
+
+    let mut top = leaderboard(env);
+    if let Some(index) = top.iter().position(|entry| entry.keeper == *keeper) {
+        top.remove(index as u32);
+    }
+    let position = top
+        .iter()
+        .position(|entry| ranks_above(&stats, &entry.stats))
+        .unwrap_or(top.len() as usize) as u32;
+    if position < MAX_RANKED_KEEPERS {
+        top.insert(
+            position,
+            KeeperRanking {
+                keeper: keeper.clone(),
+                stats,
+            },
+        );
+        if top.len() > MAX_RANKED_KEEPERS {
+            top.pop_back();
+        }
+    }
+    env.storage()
+        .persistent()
+        .set(&DataKey::RankedKeepers, &top);
+    env.storage()
+        .persistent()
+        .extend_ttl(&DataKey::RankedKeepers, ttl.threshold, ttl.extend_to);
+}
+
+fn leaderboard(env: &Env) -> Vec<KeeperRanking> {
+    env.storage()
+        .persistent()
+        .get(&DataKey::RankedKeepers)
+        .unwrap_or(Vec::new(env))
+}
+
+/// Leaderboard order: more executions first, then fewer failures, then
+/// fewer late executions.
+fn ranks_above(a: &KeeperStats, b: &KeeperStats) -> bool {
+    (a.executions, b.failures, b.late_executions) > (b.executions, a.failures, a.late_executions)
+}
+
+/// Marks a task finished and refunds its remaining gas balance to the
+/// creator, emitting `event` (`TaskFinished` on expiry, `TaskCompleted` when
+/// `max_executions` is reached).
+fn finish_task(env: &Env, task_id: u64, config: &mut TaskConfig, event: &str) {
+    let refunded = config.gas_balance;
+    config.status = TaskStatus::Finished;
+    config.gas_balance = 0;
+    store_task(env, task_id, config, &ttl_config(env));
+
+    if refunded > 0 {
+        token::Client::new(env, &fee_token(env)).transfer(
+            &env.current_contract_address(),
+            &config.creator,
+            &refunded,
+        );
+    }
+    env.events().publish(
+        (Symbol::new(env, event), task_id),
+        (config.creator.clone(), refunded),
+    );
+}
+
+/// Number of target invocations owed to a due task at `now`, and the value
+/// `last_run` takes once they are served. Zero runs means the pending slots
+/// are dropped under [`MissedRunPolicy::Skip`].
+fn pending_runs(config: &TaskConfig, now: u64) -> (u32, u64) {
+    let schedule = match config.schedule {
+        Schedule::Anchored(ref schedule) => schedule,
+        _ => return (1, now),
+    };
+
+    let due = next_run_at(config);
+    let latest = schedule.anchor + (now - schedule.anchor) / schedule.interval * schedule.interval;
+    let slots = (latest - due) / schedule.interval + 1;
+    let runs = match schedule.missed {
+        MissedRunPolicy::Skip if slots > 1 => 0,
+        MissedRunPolicy::Skip | MissedRunPolicy::RunOnce => 1,
+        MissedRunPolicy::CatchUp(cap) => slots.min(cap as u64) as u32,
+    };
+    (runs, latest)
+}
+
+/// Consults the task's resolver and returns the arguments to invoke the
+/// target with, or the outcome to report when the run is skipped.
+///
+/// Resolvers are called with try_invoke_contract so that an error inside the
+/// resolver (panic / wrong return type) is reported as `ResolverFailed`
+/// rather than aborting the whole tx.
+///
+/// A custom `resolver_function` is called with `resolver_args` spread as
+/// positional arguments, so existing view functions can act as resolvers.
+/// Otherwise the interface function's single explicit argument is the task's
+/// args vector, so we must pack config.args into a one-element outer
+/// Vec<Val> — otherwise the host would unpack config.args as individual
+/// positional arguments, causing an argument-count mismatch.
+fn resolve(env: &Env, config: &TaskConfig) -> Result<Vec<Val>, ExecutionOutcome> {
+    if !config.condition_tree.is_empty() {
+        match eval_condition(env, config, 0) {
+            Ok(true) => {}
+            Ok(false) => return Err(ExecutionOutcome::ResolverDeclined),
+            Err(()) => return Err(ExecutionOutcome::ResolverFailed),
+        }
+    }
+
+    let resolver_address = match config.resolver {
+        Some(ref resolver_address) => resolver_address,
+        None => return Ok(config.args.clone()),
+    };
+
+    let (function, resolver_call_args) = match config.resolver_function {
+        Some(ref function) => (function.clone(), config.resolver_args.clone()),
+        None => {
+            let function = match config.resolver_mode {
+                ResolverMode::Condition => Symbol::new(env, "check_condition"),
+                ResolverMode::Payload => Symbol::new(env, "check_payload"),
+            };
+            let mut resolver_call_args = Vec::<Val>::new(env);
+            resolver_call_args.push_back(config.args.clone().into_val(env));
+            (function, resolver_call_args)
+        }
+    };
+
+    match config.resolver_mode {
+        ResolverMode::Condition => match env.try_invoke_contract::<bool, soroban_sdk::Error>(
+            resolver_address,
+            &function,
+            resolver_call_args,
+        ) {
+            Ok(Ok(true)) => Ok(config.args.clone()),
+            Ok(Ok(false)) => Err(ExecutionOutcome::ResolverDeclined),
+            _ => Err(ExecutionOutcome::ResolverFailed),
+        },
+        ResolverMode::Payload => match env
+            .try_invoke_contract::<(bool, Vec<Val>), soroban_sdk::Error>(
+                resolver_address,
+                &function,
+                resolver_call_args,
+            ) {
+            Ok(Ok((true, payload))) => Ok(payload),
+            Ok(Ok((false, _))) => Err(ExecutionOutcome::ResolverDeclined),
+            _ => Err(ExecutionOutcome::ResolverFailed),
+        },
+    }
+}
+
+/// Evaluates node `index` of the task's condition tree with short-circuiting.
+///
+/// Clauses are called with try_invoke_contract; a clause that fails or
+/// returns a non-bool aborts evaluation with `Err`, so a failure is never
+/// turned into `true` by a `Not`. Relies on [`validate_conditions`] having
+/// checked the tree at registration.
+fn eval_condition(env: &Env, config: &TaskConfig, index: u32) -> Result<bool, ()> {
+    match config.condition_tree.get(index).ok_or(())? {
+        ConditionNode::Clause(clause) => {
+            let clause = config.conditions.get(clause).ok_or(())?;
+            match env.try_invoke_contract::<bool, soroban_sdk::Error>(
+                &clause.resolver,
+                &clause.function,
+                clause.args,
+            ) {
+                Ok(Ok(value)) => Ok(value),
+                _ => Err(()),
+            }
+        }
+        ConditionNode::And(children) => {
+            for child in children.iter() {
+                if !eval_condition(env, config, child)? {
+                    return Ok(false);
+                }
+            }
+            Ok(true)
+        }
+        ConditionNode::Or(children) => {
+            for child in children.iter() {
+                if eval_condition(env, config, child)? {
+                    return Ok(true);
+                }
+            }
+            Ok(false)
+        }
+        ConditionNode::Not(child) => Ok(!eval_condition(env, config, child)?),
+    }
+}
+
+/// Checks that every node of the condition tree references existing clauses
+/// and only nodes after itself, that `And`/`Or` have children, and that the
+/// tree is within [`MAX_CONDITION_NODES`].
+fn validate_conditions(config: &TaskConfig) -> Result<(), Error> {
+    let nodes = config.condition_tree.len();
+    if nodes > MAX_CONDITION_NODES {
+        return Err(Error::InvalidCondition);
+    }
+    // Every node but the root must have exactly one parent, so the tree is
+    // a proper tree and evaluation visits each node at most once.
+    let mut referenced: u64 = 0;
+    let mut adopt = |parent: u32, child: u32| {
+        let bit = 1u64 << child.min(63);
+        let ok = child > parent && child < nodes && referenced & bit == 0;
+        referenced |= bit;
+        ok
+    };
+    for (index, node) in config.condition_tree.iter().enumerate() {
+        let index = index as u32;
+        let valid = match node {
+            ConditionNode::Clause(clause) => clause < config.conditions.len(),
+            ConditionNode::And(children) | ConditionNode::Or(children) => {
+                !children.is_empty() && children.iter().all(|child| adopt(index, child))
+            }
+            ConditionNode::Not(child) => adopt(index, child),
+        };
+        if !valid {
+            return Err(Error::InvalidCondition);
+        }
+    }
+    let all_children = if nodes == 0 {
+        0
+    } else {
+        ((1u64 << nodes) - 1) & !1
+    };
+    if referenced != all_children {
+        return Err(Error::InvalidCondition);
+    }
+    Ok(())
+}
+
+/// Pays `keeper` a reward according to its [`PayoutMode`]: transferred
+/// directly, or credited to its accrued balance in the fee token.
+fn reward_keeper(env: &Env, keeper: &Address, amount: i128) {
+    if payout_mode(env, keeper) == PayoutMode::Transfer {
+        pay_keeper(env, keeper, amount);
+        return;
+    }
+    let key = DataKey::Accrued(keeper.clone(), fee_token(env));
+    let accrued: i128 = env.storage().persistent().get(&key).unwrap_or(0);
+    let ttl = ttl_config(env);
+    env.storage().persistent().set(&key, &(accrued + amount));
+    env.storage()
+        .persistent()
+        .extend_ttl(&key, ttl.threshold, ttl.extend_to);
+}
+
+fn payout_mode(env: &Env, keeper: &Address) -> PayoutMode {
+    env.storage()
+        .persistent()
+        .get(&DataKey::PayoutMode(keeper.clone()))
+        .unwrap_or(PayoutMode::Transfer)
+}
+
+/// Transfers `amount` of the fee token from the contract's escrow to `keeper`.
+fn pay_keeper(env: &Env, keeper: &Address, amount: i128) {
+    token::Client::new(env, &fee_token(env)).transfer(
+        &env.current_contract_address(),
+        keeper,
+        &amount,
+    );
+}
+
+fn admin(env: &Env) -> Address {
+    env.storage()
+        .instance()
+        .get(&DataKey::Admin)
+        .unwrap_or_else(|| panic_with_error!(env, Error::NotInitialized))
+}
+
+fn ttl_config(env: &Env) -> TtlConfig {
+    env.storage()
+        .instance()
+        .get(&DataKey::TtlConfig)
+        .unwrap_or(TtlConfig {
+            threshold: 30 * DAY_IN_LEDGERS,
+            extend_to: 120 * DAY_IN_LEDGERS,
+            bump_fee: 0,
+        })
+}
+
+fn extend_instance(env: &Env, ttl: &TtlConfig) {
+    env.storage()
+        .instance()
+        .extend_ttl(ttl.threshold, ttl.extend_to);
+}
+
+/// Whether the task entry has fewer than `ttl.threshold` ledgers left.
+fn needs_extension(env: &Env, config: &TaskConfig, ttl: &TtlConfig) -> bool {
+    config.live_until.saturating_sub(env.ledger().sequence()) < ttl.threshold
+}
+
+/// Writes a task and, when it is within the TTL threshold, extends its entry
+/// to `ttl.extend_to` ledgers, recording the new expiry in `live_until`.
+fn store_task(env: &Env, task_id: u64, config: &mut TaskConfig, ttl: &TtlConfig) {
+    let key = DataKey::Task(task_id);
+    let extend = needs_extension(env, config, ttl);
+    if extend {
+        config.live_until = env.ledger().sequence().saturating_add(ttl.extend_to);
+    }
+    env.storage().persistent().set(&key, config);
+    if extend {
+        env.storage()
+            .persistent()
+            .extend_ttl(&key, ttl.extend_to, ttl.extend_to);
+    }
+}
+
+fn keeper_config(env: &Env) -> KeeperConfig {
+    env.storage()
+        .instance()
+        .get(&DataKey::KeeperConfig)
+        .unwrap_or(KeeperConfig {
+            min_stake: 1,
+            unbonding_period: 7 * 86_400,
+            grace_period: 3_600,
+            slash_bps: 1_000,
+            reporter_share_bps: 5_000,
+            exclusive_window: 60,
+        })
+}
+
+fn load_keeper(env: &Env, keeper: &Address) -> KeeperInfo {
+    env.storage()
+        .persistent()
+        .get(&DataKey::Keeper(keeper.clone()))
+        .unwrap_or_else(|| panic_with_error!(env, Error::KeeperNotRegistered))
+}
+
+fn store_keeper(env: &Env, keeper: &Address, info: &KeeperInfo) {
+    let key = DataKey::Keeper(keeper.clone());
+    let ttl = ttl_config(env);
+    env.storage().persistent().set(&key, info);
+    env.storage()
+        .persistent()
+        .extend_ttl(&key, ttl.threshold, ttl.extend_to);
+}
+
+fn store_keepers(env: &Env, keepers: &Vec<Address>) {
+    let ttl = ttl_config(env);
+    env.storage().persistent().set(&DataKey::Keepers, keepers);
+    env.storage()
+        .persistent()
+        .extend_ttl(&DataKey::Keepers, ttl.threshold, ttl.extend_to);
+}
+
+/// The keeper with the exclusive right to a task's run due at `due_at`.
+fn designated_keeper(env: &Env, task_id: u64, config: &TaskConfig, due_at: u64) -> Option<Address> {
+    if let Some(assigned) = &config.assigned_keeper {
+        if is_bonded(env, assigned) && is_allowed(config, assigned) {
+            return Some(assigned.clone());
+        }
+    }
+    // The rotation covers the whole registry; tasks with an allowlist are
+    // only reserved for a keeper that claimed them.
+    if !config.allowed_keepers.is_empty() {
+        return None;
+    }
+    // Only keepers that still meet the bonding policy take part; a keeper
+    // slashed or left below `min_stake` stays listed but gets no window.
+    let mut keepers = Vec::<Address>::new(env);
+    for keeper in SoroTaskContract::active_keepers(env.clone()).iter() {
+        if is_bonded(env, &keeper) {
+            keepers.push_back(keeper);
+        }
+    }
+    if keepers.is_empty() {
+        return None;
+    }
+    let mut seed = [0u8; 16];
+    seed[..8].copy_from_slice(&task_id.to_be_bytes());
+    seed[8..].copy_from_slice(&due_at.to_be_bytes());
+    let digest = env
+        .crypto()
+        .sha256(&Bytes::from_array(env, &seed))
+        .to_array();
+    let mut head = [0u8; 8];
+    head.copy_from_slice(&digest[..8]);
+    let slot = u64::from_be_bytes(head) % keepers.len() as u64;
+    keepers.get(slot as u32)
+}
+
+/// Unassigns the task's keeper once the allowlist no longer admits it, so
+/// it is not held to runs it may not perform.
+fn drop_disallowed_assignment(config: &mut TaskConfig) {
+    if let Some(assigned) = &config.assigned_keeper {
+        if !is_allowed(config, assigned) {
+            config.assigned_keeper = None;
+        }
+    }
+}
+
+/// Whether the task's allowlist admits `keeper`.
+fn is_allowed(config: &TaskConfig, keeper: &Address) -> bool {
+    config.allowed_keepers.is_empty() || config.allowed_keepers.contains(keeper)
+}
+
+/// Whether `keeper` holds an active bond of at least the current minimum
+/// stake.
+fn is_bonded(env: &Env, keeper: &Address) -> bool {
+    let info: Option<KeeperInfo> = env
+        .storage()
+        .persistent()
+        .get(&DataKey::Keeper(keeper.clone()));
+    info.is_some_and(|info| {
+        info.unbonds_at.is_none() && info.stake > 0 && info.stake >= keeper_config(env).min_stake
+    })
+}
+
+fn fee_token(env: &Env) -> Address {
+    env.storage()
+        .instance()
+        .get(&DataKey::Token)
+        .unwrap_or_else(|| panic_with_error!(env, Error::NotInitialized))
+}
+
+/// Transitions a task between active and paused, emitting `TaskPaused` or
+/// `TaskResumed`.
+fn set_status(env: &Env, task_id: u64, status: TaskStatus) {
+    let mut config = load_unfinished_task(env, task_id);
+    config.creator.require_auth();
+
+    if config.status == status {
+        match status {
+            TaskStatus::Paused => panic_with_error!(env, Error::TaskPaused),
+            _ => panic_with_error!(env, Error::TaskNotPaused),
+        }
+    }
+
+    config.status = status;
+    // A paused task could not be run; the grace period restarts.
+    config.assigned_at = env.ledger().timestamp();
+    env.storage()
+        .persistent()
+        .set(&DataKey::Task(task_id), &config);
+
+    let event = match status {
+        TaskStatus::Paused => "TaskPaused",
+        _ => "TaskResumed",
+    };
+    env.events()
+        .publish((Symbol::new(env, event), task_id), config.creator);
+}
+
+/// Earliest timestamp at which the task may run again, never before
+/// `start_at`; `u64::MAX` for a cron schedule with no slot within the search
+/// bound.
+fn next_run_at(config: &TaskConfig) -> u64 {
+    // Calendar and anchored slots before `start_at` are not eligible, so
+    // search from just before it.
+    let after = config.last_run.max(config.start_at.saturating_sub(1));
+    match config.schedule {
+        Schedule::Interval(interval) => config
+            .last_run
+            .saturating_add(interval)
+            .max(config.start_at),
+        Schedule::Cron(ref schedule) => cron::next_after(schedule, after).unwrap_or(u64::MAX),
+        Schedule::Anchored(ref schedule) => {
+            // First slot strictly after `after`.
+            if after < schedule.anchor {
+                schedule.anchor
+            } else {
+                let served = (after - schedule.anchor) / schedule.interval;
+                schedule
+                    .anchor
+                    .saturating_add((served + 1).saturating_mul(schedule.interval))
+            }
+        }
+    }
+}
+
+fn validate_schedule(schedule: &Schedule) -> Result<(), Error> {
+    match schedule {
+        Schedule::Interval(0) => Err(Error::InvalidInterval),
+        Schedule::Interval(_) => Ok(()),
+        Schedule::Cron(schedule) => cron::validate(schedule),
+        Schedule::Anchored(schedule) if schedule.interval == 0 => Err(Error::InvalidInterval),
+        Schedule::Anchored(schedule) if schedule.missed == MissedRunPolicy::CatchUp(0) => {
+            Err(Error::InvalidSchedule)
+        }
+        Schedule::Anchored(_) => Ok(()),
+    }
+}
+
+/// Builds the stored form of a new task, with contract-owned state reset.
+fn new_task(params: TaskParams) -> TaskConfig {
+    TaskConfig {
+        creator: params.creator,
+        target: params.target,
+        function: params.function,
+        args: params.args,
+        resolver: params.resolver,
+        resolver_mode: params.resolver_mode,
+        resolver_function: params.resolver_function,
+        resolver_args: params.resolver_args,
+        conditions: params.conditions,
+        condition_tree: params.condition_tree,
+        schedule: params.schedule,
+        last_run: 0,
+        start_at: params.start_at,
+        end_at: params.end_at,
+        max_executions: params.max_executions,
+        execution_count: 0,
+        bonded_only: params.bonded_only,
+        allowed_keepers: params.allowed_keepers,
+        assigned_keeper: None,
+        assigned_at: 0,
+        gas_balance: params.gas_balance,
+        fee: params.fee,
+        fee_policy: params.fee_policy,
+        status: TaskStatus::Active,
+        live_until: 0,
+    }
+}
+
+fn load_task(env: &Env, task_id: u64) -> TaskConfig {
+    env.storage()
+        .persistent()
+        .get(&DataKey::Task(task_id))
+        .unwrap_or_else(|| panic_with_error!(env, Error::TaskNotFound))
+}
+
+/// Loads a task that may still be changed, failing with
+/// [`Error::TaskFinished`] once it has finished.
+fn load_unfinished_task(env: &Env, task_id: u64) -> TaskConfig {
+    let config = load_task(env, task_id);
+    if config.status == TaskStatus::Finished {
+        panic_with_error!(env, Error::TaskFinished);
+    }
+    config
+}
+
+/// Whether the task's `end_at` has been reached.
+fn is_expired(env: &Env, config: &TaskConfig) -> bool {
+    config
+        .end_at
+        .is_some_and(|end_at| env.ledger().timestamp() >= end_at)
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use soroban_sdk::{
+        contract, contractimpl,
+        testutils::{storage::Persistent as _, Address as _, Events, Ledger as _},
+        token::StellarAssetClient,
+        vec, Env, FromVal, IntoVal, String,
+    };
+
+    // ── Mock Contracts ───────────────────────────────────────────────────────
+
+    #[contract]
+    pub struct DummyContract;
+
+    #[contractimpl]
+    impl DummyContract {
+        pub fn hello(_env: Env) {}
+    }
+
+    /// Minimal target contract with two callable functions.
+    #[contract]
+    pub struct MockTarget;
+
+    #[contractimpl]
+    impl MockTarget {
+        /// Zero-argument smoke-test function.
+        pub fn ping(_env: Env) -> bool {
+            true
+        }
+
+        /// Two-argument function — verifies args are forwarded correctly.
+        pub fn add(_env: Env, a: i64, b: i64) -> i64 {
+            a + b
+        }
+
+        /// Records `a + b` so tests can observe which args were forwarded.
+        pub fn record(env: Env, a: i64, b: i64) {
+            env.storage()
+                .instance()
+                .set(&Symbol::new(&env, "last"), &(a + b));
+        }
+
+        pub fn recorded(env: Env) -> Option<i64> {
+            env.storage().instance().get(&Symbol::new(&env, "last"))
+        }
+
+        /// Counts its invocations so tests can observe catch-up runs.
+        pub fn tick(env: Env) {
+            let key = Symbol::new(&env, "ticks");
+            let ticks: u32 = env.storage().instance().get(&key).unwrap_or(0);
+            env.storage().instance().set(&key, &(ticks + 1));
+        }
+
+        pub fn ticks(env: Env) -> u32 {
+            env.storage()
+                .instance()
+                .get(&Symbol::new(&env, "ticks"))
+                .unwrap_or(0)
+        }
+
+        /// Always traps — simulates a broken target.
+        pub fn fail(_env: Env) {
+            panic!("target failure")
+        }
+    }
+
+    // ── Resolver contracts (separate sub-modules) ───────────────────────
+
+    /// Resolver that always approves execution.
+    mod resolver_true {
+        use soroban_sdk::{contract, contractimpl, Env, Val, Vec};
+
+        #[contract]
+        pub struct MockResolverTrue;
+
+        #[contractimpl]
+        impl MockResolverTrue {
+            pub fn check_condition(_env: Env, _args: Vec<Val>) -> bool {
+                true
+            }
+        }
+    }
+
+    /// Resolver that always denies execution.
+    mod resolver_false {
+        use soroban_sdk::{contract, contractimpl, Env, Val, Vec};
+
+        #[contract]
+        pub struct MockResolverFalse;
+
+        #[contractimpl]
+        impl MockResolverFalse {
+            pub fn check_condition(_env: Env, _args: Vec<Val>) -> bool {
+                false
+            }
+        }
+    }
+
+    /// Resolver whose check always traps.
+    mod resolver_panic {
+        use soroban_sdk::{contract, contractimpl, Env, Val, Vec};
+
+        #[contract]
+        pub struct MockResolverPanic;
+
+        #[contractimpl]
+        impl MockResolverPanic {
+            pub fn check_condition(_env: Env, _args: Vec<Val>) -> bool {
+                panic!("resolver failure")
+            }
+        }
+    }
+
+    /// Payload resolver that doubles each i64 in the task args.
+    mod resolver_payload {
+        use soroban_sdk::{contract, contractimpl, Env, TryFromVal, Val, Vec};
+
+        #[contract]
+        pub struct MockPayloadResolver;
+
+        #[contractimpl]
+        impl MockPayloadResolver {
+            pub fn check_payload(env: Env, args: Vec<Val>) -> (bool, Vec<Val>) {
+                let mut doubled = Vec::new(&env);
+                for arg in args.iter() {
+                    let n = i64::try_from_val(&env, &arg).unwrap();
+                    doubled.push_back(Val::try_from_val(&env, &(n * 2)).unwrap());
+                }
+                (true, doubled)
+            }
+        }
+    }
+
+    /// Payload resolver that always declines.
+    mod resolver_payload_false {
+        use soroban_sdk::{contract, contractimpl, Env, Val, Vec};
+
+        #[contract]
+        pub struct MockPayloadResolverFalse;
+
+        #[contractimpl]
+        impl MockPayloadResolverFalse {
+            pub fn check_payload(env: Env, _args: Vec<Val>) -> (bool, Vec<Val>) {
+                (false, Vec::new(&env))
+            }
+        }
+    }
+
+    /// Price oracle exposing its own view functions, used as a resolver
+    /// without an adapter contract.
+    mod oracle {
+        use soroban_sdk::{contract, contractimpl, vec, Env, IntoVal, Val, Vec};
+
+        #[contract]
+        pub struct MockOracle;
+
+        #[contractimpl]
+        impl MockOracle {
+            pub fn price(_env: Env) -> i64 {
+                100
+            }
+
+            pub fn price_between(_env: Env, low: i64, high: i64) -> bool {
+                (low..=high).contains(&100)
+            }
+
+            /// Payload-style view: returns the price as the target's args.
+            pub fn quote(env: Env, offset: i64) -> (bool, Vec<Val>) {
+                (
+                    true,
+                    vec![&env, 100_i64.into_val(&env), offset.into_val(&env)],
+                )
+            }
+        }
+    }
+
+    /// Clause resolvers with fixed answers and a call counter for observing
+    /// short-circuiting.
+    mod clauses {
+        use soroban_sdk::{contract, contractimpl, symbol_short, Env};
+
+        #[contract]
+        pub struct MockClauses;
+
+        #[contractimpl]
+        impl MockClauses {
+            pub fn yes(_env: Env) -> bool {
+                true
+            }
+
+            pub fn no(_env: Env) -> bool {
+                false
+            }
+
+            pub fn boom(_env: Env) -> bool {
+                panic!("clause failure")
+            }
+
+            /// Returns `value` and counts the call.
+            pub fn counted(env: Env, value: bool) -> bool {
+                let calls: u32 = env
+                    .storage()
+                    .instance()
+                    .get(&symbol_short!("calls"))
+                    .unwrap_or(0);
+                env.storage()
+                    .instance()
+                    .set(&symbol_short!("calls"), &(calls + 1));
+                value
+            }
+
+            pub fn calls(env: Env) -> u32 {
+                env.storage()
+                    .instance()
+                    .get(&symbol_short!("calls"))
+                    .unwrap_or(0)
+            }
+        }
+    }
+
+    // ── Helpers ──────────────────────────────────────────────────────────────
+
+    fn setup() -> (Env, Address) {
+        let env = Env::default();
+        env.mock_all_auths();
+        let id = env.register_contract(None, SoroTaskContract);
+        init(&env, &id);
+        (env, id)
+    }
+
+    /// Deploys a Stellar asset as the fee token and initializes SoroTask with it.
+    fn init(env: &Env, contract_id: &Address) -> Address {
+        let admin = Address::generate(env);
+        let token = env.register_stellar_asset_contract_v2(admin.clone());
+        SoroTaskContractClient::new(env, contract_id).initialize(&admin, &token.address());
+        token.address()
+    }
+
+    fn mint(env: &Env, token: &Address, to: &Address, amount: i128) {
+        StellarAssetClient::new(env, token).mint(to, &amount);
+    }
+
+    fn balance(env: &Env, token: &Address, of: &Address) -> i128 {
+        soroban_sdk::token::Client::new(env, token).balance(of)
+    }
+
+    /// Mints the initial gas deposit to the creator, then registers the task.
+    fn register_funded(env: &Env, client: &SoroTaskContractClient, cfg: &TaskParams) -> u64 {
+        mint(env, &client.get_token(), &cfg.creator, cfg.gas_balance);
+        client.register(cfg)
+    }
+
+    fn base_config(env: &Env, target: Address) -> TaskParams {
+        TaskParams {
+            creator: Address::generate(env),
+            target,
+            function: Symbol::new(env, "ping"),
+            args: Vec::new(env),
+            resolver: None,
+            resolver_mode: ResolverMode::Condition,
+            resolver_function: None,
+            resolver_args: Vec::new(env),
+            conditions: Vec::new(env),
+            condition_tree: Vec::new(env),
+            schedule: Schedule::Interval(3_600),
+            start_at: 0,
+            end_at: None,
+            max_executions: None,
+            bonded_only: false,
+            allowed_keepers: Vec::new(env),
+            gas_balance: 1_000,
+            fee: 10,
+            fee_policy: FeePolicy::Fixed,
+        }
+    }
+
+    fn set_sequence(env: &Env, seq: u32) {
+        env.ledger().with_mut(|l| l.sequence_number = seq);
+    }
+
+    fn task_ttl(env: &Env, contract_id: &Address, task_id: u64) -> u32 {
+        env.as_contract(contract_id, || {
+            env.storage().persistent().get_ttl(&DataKey::Task(task_id))
+        })
+    }
+
+    fn set_timestamp(env: &Env, ts: u64) {
+        env.ledger().with_mut(|l| l.timestamp = ts);
+    }
+
+    /// First topic of the most recent contract event.
+    fn last_event_name(env: &Env) -> Symbol {
+        let events = env.events().all();
+        Symbol::from_val(env, &events.last().unwrap().1.get(0).unwrap())
+    }
+
+    // ── Tests ─────────────────────────────────────────────────────────────────
+
+    /// Registering a task stores it; get_task retrieves identical data.
+    #[test]
+    fn test_register_and_get_task() {
+        let (env, id) = setup();
+        let client = SoroTaskContractClient::new(&env, &id);
+
+        let target = env.register_contract(None, MockTarget);
+        let cfg = base_config(&env, target.clone());
+        let task_id = register_funded(&env, &client, &cfg);
+
+        let stored = client.get_task(&task_id).expect("task should exist");
+        assert_eq!(stored.target, target);
+        assert_eq!(stored.schedule, Schedule::Interval(3_600));
+        assert_eq!(stored.last_run, 0, "last_run must start at 0");
+    }
+
+    /// Querying a task id that was never registered returns None.
+    #[test]
+    fn test_get_task_missing_returns_none() {
+        let (env, id) = setup();
+        let client = SoroTaskContractClient::new(&env, &id);
+        assert!(client.get_task(&99_u64).is_none());
+    }
+
+    /// A successful cross-contract call updates last_run to the ledger timestamp.
+    #[test]
+    fn test_execute_invokes_target_and_updates_last_run() {
+        let (env, id) = setup();
+        let client = SoroTaskContractClient::new(&env, &id);
+        let keeper = Address::generate(&env);
+
+        let target = env.register_contract(None, MockTarget);
+        let task_id = register_funded(&env, &client, &base_config(&env, target));
+
+        set_timestamp(&env, 12_345);
+        client.execute(&keeper, &task_id);
+
+        let updated = client.get_task(&task_id).unwrap();
+        assert_eq!(
+            updated.last_run, 12_345,
+            "last_run must reflect ledger timestamp after execution"
+        );
+    }
+
+    /// Args stored in TaskConfig are forwarded correctly to the target function.
+    #[test]
+    fn test_execute_forwards_args_to_target() {
+        let (env, id) = setup();
+        let client = SoroTaskContractClient::new(&env, &id);
+        let keeper = Address::generate(&env);
+
+        let target = env.register_contract(None, MockTarget);
+
+        let mut args: Vec<Val> = Vec::new(&env);
+        args.push_back(5_i64.into_val(&env));
+        args.push_back(3_i64.into_val(&env));
+
+        let cfg = TaskParams {
+            creator: Address::generate(&env),
+            target,
+            function: Symbol::new(&env, "add"),
+            args,
+            resolver: None,
+            resolver_mode: ResolverMode::Condition,
+            resolver_function: None,
+            resolver_args: Vec::new(&env),
+            conditions: Vec::new(&env),
+            condition_tree: Vec::new(&env),
+            schedule: Schedule::Interval(60),
+            start_at: 0,
+            end_at: None,
+            max_executions: None,
+            bonded_only: false,
+            allowed_keepers: Vec::new(&env),
+            gas_balance: 500,
+            fee: 10,
+            fee_policy: FeePolicy::Fixed,
+        };
+
+        let task_id = register_funded(&env, &client, &cfg);
+        set_timestamp(&env, 99_999);
+        client.execute(&keeper, &task_id);
+
+        assert_eq!(client.get_task(&task_id).unwrap().last_run, 99_999);
+    }
+
+    /// When a resolver returns true the target is invoked and last_run updated.
+    #[test]
+    fn test_execute_with_resolver_true_proceeds() {
+        let (env, id) = setup();
+        let client = SoroTaskContractClient::new(&env, &id);
+        let keeper = Address::generate(&env);
+
+        let target = env.register_contract(None, MockTarget);
+        let resolver = env.register_contract(None, resolver_true::MockResolverTrue);
+
+        let cfg = TaskParams {
+            resolver: Some(resolver),
+            ..base_config(&env, target)
+        };
+
+        let task_id = register_funded(&env, &client, &cfg);
+        set_timestamp(&env, 55_000);
+        client.execute(&keeper, &task_id);
+
+        assert_eq!(
+            client.get_task(&task_id).unwrap().last_run,
+            55_000,
+            "resolver approved — last_run must be updated"
+        );
+    }
+
+    /// When a resolver returns false the target is NOT invoked and last_run is
+    /// left unchanged.
+    #[test]
+    fn test_execute_with_resolver_false_skips_invocation() {
+        let (env, id) = setup();
+        let client = SoroTaskContractClient::new(&env, &id);
+        let keeper = Address::generate(&env);
+
+        let target = env.register_contract(None, MockTarget);
+        let resolver = env.register_contract(None, resolver_false::MockResolverFalse);
+
+        let cfg = TaskParams {
+            resolver: Some(resolver),
+            ..base_config(&env, target)
+        };
+
+        let task_id = register_funded(&env, &client, &cfg);
+        set_timestamp(&env, 77_777);
+        assert_eq!(
+            client.execute(&keeper, &task_id),
+            ExecutionOutcome::ResolverDeclined
+        );
+
+        assert_eq!(
+            client.get_task(&task_id).unwrap().last_run,
+            0,
+            "resolver denied — last_run must not change"
+        );
+    }
+
+    /// Calling execute multiple times updates last_run on every successful run.
+    #[test]
+    fn test_execute_repeated_calls_update_timestamp_each_time() {
+        let (env, id) = setup();
+        let client = SoroTaskContractClient::new(&env, &id);
+        let keeper = Address::generate(&env);
+
+        let target = env.register_contract(None, MockTarget);
+        let mut cfg = base_config(&env, target);
+        cfg.schedule = Schedule::Interval(1); // Small interval to allow repeated execution
+        let task_id = register_funded(&env, &client, &cfg);
+
+        set_timestamp(&env, 1_000);
+        client.execute(&keeper, &task_id);
+        assert_eq!(client.get_task(&task_id).unwrap().last_run, 1_000);
+
+        set_timestamp(&env, 2_000);
+        client.execute(&keeper, &task_id);
+        assert_eq!(
+            client.get_task(&task_id).unwrap().last_run,
+            2_000,
+            "last_run must advance on each execution"
+        );
+    }
+
+    #[test]
+    fn test_register_and_get() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let contract_id = env.register_contract(None, SoroTaskContract);
+        init(&env, &contract_id);
+        let client = SoroTaskContractClient::new(&env, &contract_id);
+
+        let creator = Address::generate(&env);
+        let target = Address::generate(&env);
+
+        let config = TaskParams {
+            creator: creator.clone(),
+            target: target.clone(),
+            function: Symbol::new(&env, "hello"),
+            args: vec![&env, 0i128.into_val(&env)],
+            resolver: None,
+            resolver_mode: ResolverMode::Condition,
+            resolver_function: None,
+            resolver_args: Vec::new(&env),
+            conditions: Vec::new(&env),
+            condition_tree: Vec::new(&env),
+            schedule: Schedule::Interval(3600),
+            start_at: 0,
+            end_at: None,
+            max_executions: None,
+            bonded_only: false,
+            allowed_keepers: Vec::new(&env),
+            gas_balance: 1000,
+            fee: 10,
+            fee_policy: FeePolicy::Fixed,
+        };
+
+        let task_id = register_funded(&env, &client, &config);
+        assert_eq!(task_id, 1);
+
+        let retrieved_config = client.get_task(&task_id).unwrap();
+        assert_eq!(retrieved_config.creator, config.creator);
+        assert_eq!(retrieved_config.target, config.target);
+        assert_eq!(retrieved_config.function, config.function);
+        assert_eq!(retrieved_config.schedule, config.schedule);
+        assert_eq!(retrieved_config.gas_balance, config.gas_balance);
+
+        // Check event
+        let events = env.events().all();
+        let last_event = events.last().unwrap();
+
+        assert_eq!(last_event.0, contract_id);
+
+        let topics = last_event.1.clone();
+        assert_eq!(
+            Symbol::from_val(&env, &topics.get(0).unwrap()),
+            Symbol::new(&env, "TaskRegistered")
+        );
+        assert_eq!(u64::from_val(&env, &topics.get(1).unwrap()), 1u64);
+
+        let data: Address = last_event.2.clone().into_val(&env);
+        assert_eq!(data, creator);
+    }
+
+    #[test]
+    fn test_sequential_ids() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let contract_id = env.register_contract(None, SoroTaskContract);
+        init(&env, &contract_id);
+        let client = SoroTaskContractClient::new(&env, &contract_id);
+
+        let creator = Address::generate(&env);
+        let target = Address::generate(&env);
+
+        let config = TaskParams {
+            creator: creator.clone(),
+            target: target.clone(),
+            function: Symbol::new(&env, "hello"),
+            args: vec![&env],
+            resolver: None,
+            resolver_mode: ResolverMode::Condition,
+            resolver_function: None,
+            resolver_args: Vec::new(&env),
+            conditions: Vec::new(&env),
+            condition_tree: Vec::new(&env),
+            schedule: Schedule::Interval(3600),
+            start_at: 0,
+            end_at: None,
+            max_executions: None,
+            bonded_only: false,
+            allowed_keepers: Vec::new(&env),
+            gas_balance: 1000,
+            fee: 10,
+            fee_policy: FeePolicy::Fixed,
+        };
+
+        let id1 = register_funded(&env, &client, &config);
+        let id2 = register_funded(&env, &client, &config);
+
+        assert_eq!(id1, 1);
+        assert_eq!(id2, 2);
+    }
+
+    #[test]
+    fn test_register_invalid_interval() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let contract_id = env.register_contract(None, SoroTaskContract);
+        init(&env, &contract_id);
+        let client = SoroTaskContractClient::new(&env, &contract_id);
+
+        let creator = Address::generate(&env);
+        let target = Address::generate(&env);
+
+        let config = TaskParams {
+            creator: creator.clone(),
+            target: target.clone(),
+            function: Symbol::new(&env, "hello"),
+            args: vec![&env],
+            resolver: None,
+            resolver_mode: ResolverMode::Condition,
+            resolver_function: None,
+            resolver_args: Vec::new(&env),
+            conditions: Vec::new(&env),
+            condition_tree: Vec::new(&env),
+            schedule: Schedule::Interval(0), // Invalid
+            start_at: 0,
+            end_at: None,
+            max_executions: None,
+            bonded_only: false,
+            allowed_keepers: Vec::new(&env),
+            gas_balance: 1000,
+            fee: 10,
+            fee_policy: FeePolicy::Fixed,
+        };
+
+        let result = client.try_register(&config);
+        assert_eq!(result, Err(Ok(soroban_sdk::Error::from_contract_error(1))));
+    }
+
+    #[test]
+    fn test_execute_honors_interval() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let contract_id = env.register_contract(None, SoroTaskContract);
+        init(&env, &contract_id);
+        let client = SoroTaskContractClient::new(&env, &contract_id);
+        let keeper = Address::generate(&env);
+
+        let creator = Address::generate(&env);
+        let dummy_id = env.register_contract(None, DummyContract);
+        let target = dummy_id.clone();
+
+        let config = TaskParams {
+            creator: creator.clone(),
+            target: target.clone(),
+            function: Symbol::new(&env, "hello"),
+            args: Vec::new(&env),
+            resolver: None,
+            resolver_mode: ResolverMode::Condition,
+            resolver_function: None,
+            resolver_args: Vec::new(&env),
+            conditions: Vec::new(&env),
+            condition_tree: Vec::new(&env),
+            schedule: Schedule::Interval(100),
+            start_at: 0,
+            end_at: None,
+            max_executions: None,
+            bonded_only: false,
+            allowed_keepers: Vec::new(&env),
+            gas_balance: 1000,
+            fee: 10,
+            fee_policy: FeePolicy::Fixed,
+        };
+
+        let task_id = register_funded(&env, &client, &config);
+
+        // First execution (ledger 50, last_run 0, interval 100)
+        // 50 < 0 + 100 -> NotDue, due again at 100
+        env.ledger().set_timestamp(50);
+        assert_eq!(
+            client.execute(&keeper, &task_id),
+            ExecutionOutcome::NotDue(100)
+        );
+        assert_eq!(client.get_task(&task_id).unwrap().last_run, 0);
+
+        env.ledger().set_timestamp(150);
+        client.execute(&keeper, &task_id);
+        assert_eq!(client.get_task(&task_id).unwrap().last_run, 150);
+
+        // Next execution too soon
+        env.ledger().set_timestamp(200);
+        assert_eq!(
+            client.execute(&keeper, &task_id),
+            ExecutionOutcome::NotDue(250)
+        );
+        assert_eq!(client.get_task(&task_id).unwrap().last_run, 150);
+    }
+
+    // ── Gas escrow ───────────────────────────────────────────────────────────
+
+    /// The initial gas deposit moves from the creator into the contract.
+    #[test]
+    fn test_register_escrows_gas_balance() {
+        let (env, id) = setup();
+        let client = SoroTaskContractClient::new(&env, &id);
+        let token = client.get_token();
+
+        let cfg = base_config(&env, Address::generate(&env));
+        mint(&env, &token, &cfg.creator, 1_500);
+        let task_id = client.register(&cfg);
+
+        assert_eq!(balance(&env, &token, &cfg.creator), 500);
+        assert_eq!(balance(&env, &token, &id), 1_000);
+        assert_eq!(client.get_task(&task_id).unwrap().gas_balance, 1_000);
+    }
+
+    /// Registration fails when the creator cannot fund the declared balance.
+    #[test]
+    fn test_register_without_funds_fails() {
+        let (env, id) = setup();
+        let client = SoroTaskContractClient::new(&env, &id);
+
+        let cfg = base_config(&env, Address::generate(&env));
+        assert!(client.try_register(&cfg).is_err());
+        assert!(client.get_task(&1).is_none());
+    }
+
+    #[test]
+    fn test_register_negative_gas_balance() {
+        let (env, id) = setup();
+        let client = SoroTaskContractClient::new(&env, &id);
+
+        let mut cfg = base_config(&env, Address::generate(&env));
+        cfg.gas_balance = -1;
+        assert_eq!(
+            client.try_register(&cfg),
+            Err(Ok(Error::InvalidAmount.into()))
+        );
+    }
+
+    #[test]
+    fn test_register_before_initialize() {
+        let env = Env::default();
+        env.mock_all_auths();
+        let id = env.register_contract(None, SoroTaskContract);
+        let client = SoroTaskContractClient::new(&env, &id);
+
+        let cfg = base_config(&env, Address::generate(&env));
+        assert_eq!(
+            client.try_register(&cfg),
+            Err(Ok(Error::NotInitialized.into()))
+        );
+    }
+
+    #[test]
+    fn test_initialize_twice() {
+        let (env, id) = setup();
+        let client = SoroTaskContractClient::new(&env, &id);
+
+        let admin = Address::generate(&env);
+        let token = Address::generate(&env);
+        assert_eq!(
+            client.try_initialize(&admin, &token),
+            Err(Ok(Error::AlreadyInitialized.into()))
+        );
+    }
+
+    #[test]
+    fn test_deposit_gas() {
+        let (env, id) = setup();
+        let client = SoroTaskContractClient::new(&env, &id);
+        let token = client.get_token();
+
+        let cfg = base_config(&env, Address::generate(&env));
+        let task_id = register_funded(&env, &client, &cfg);
+
+        mint(&env, &token, &cfg.creator, 250);
+        client.deposit_gas(&task_id, &250);
+
+        assert_eq!(client.get_task(&task_id).unwrap().gas_balance, 1_250);
+        assert_eq!(balance(&env, &token, &cfg.creator), 0);
+        assert_eq!(balance(&env, &token, &id), 1_250);
+    }
+
+    #[test]
+    fn test_deposit_gas_requires_creator_auth() {
+        let (env, id) = setup();
+        let client = SoroTaskContractClient::new(&env, &id);
+
+        let cfg = base_config(&env, Address::generate(&env));
+        let task_id = register_funded(&env, &client, &cfg);
+        mint(&env, &client.get_token(), &cfg.creator, 100);
+        client.deposit_gas(&task_id, &100);
+
+        let auths = env.auths();
+        assert_eq!(auths[0].0, cfg.creator);
+    }
+
+    #[test]
+    fn test_deposit_gas_invalid_amount() {
+        let (env, id) = setup();
+        let client = SoroTaskContractClient::new(&env, &id);
+
+        let cfg = base_config(&env, Address::generate(&env));
+        let task_id = register_funded(&env, &client, &cfg);
+        assert_eq!(
+            client.try_deposit_gas(&task_id, &0),
+            Err(Ok(Error::InvalidAmount.into()))
+        );
+    }
+
+    #[test]
+    fn test_deposit_gas_missing_task() {
+        let (env, id) = setup();
+        let client = SoroTaskContractClient::new(&env, &id);
+        assert_eq!(
+            client.try_deposit_gas(&42, &10),
+            Err(Ok(Error::TaskNotFound.into()))
+        );
+    }
+
+    #[test]
+    fn test_withdraw_gas() {
+        let (env, id) = setup();
+        let client = SoroTaskContractClient::new(&env, &id);
+        let token = client.get_token();
+
+        let cfg = base_config(&env, Address::generate(&env));
+        let task_id = register_funded(&env, &client, &cfg);
+
+        client.withdraw_gas(&task_id, &400);
+
+        assert_eq!(client.get_task(&task_id).unwrap().gas_balance, 600);
+        assert_eq!(balance(&env, &token, &cfg.creator), 400);
+        assert_eq!(balance(&env, &token, &id), 600);
+    }
+
+    #[test]
+    fn test_withdraw_gas_exceeding_balance() {
+        let (env, id) = setup();
+        let client = SoroTaskContractClient::new(&env, &id);
+
+        let cfg = base_config(&env, Address::generate(&env));
+        let task_id = register_funded(&env, &client, &cfg);
+        assert_eq!(
+            client.try_withdraw_gas(&task_id, &1_001),
+            Err(Ok(Error::InsufficientBalance.into()))
+        );
+        assert_eq!(client.get_task(&task_id).unwrap().gas_balance, 1_000);
+    }
+
+    // ── Keeper rewards ───────────────────────────────────────────────────────
+
+    /// A successful run moves `fee` from the escrow to the keeper.
+    #[test]
+    fn test_execute_pays_keeper() {
+        let (env, id) = setup();
+        let client = SoroTaskContractClient::new(&env, &id);
+        let keeper = Address::generate(&env);
+        let token = client.get_token();
+
+        let target = env.register_contract(None, MockTarget);
+        let task_id = register_funded(&env, &client, &base_config(&env, target));
+
+        set_timestamp(&env, 5_000);
+        client.execute(&keeper, &task_id);
+
+        assert_eq!(balance(&env, &token, &keeper), 10);
+        assert_eq!(balance(&env, &token, &id), 990);
+        assert_eq!(client.get_task(&task_id).unwrap().gas_balance, 990);
+
+        let events = env.events().all();
+        let last_event = events.last().unwrap();
+        assert_eq!(last_event.0, id);
+        assert_eq!(
+            Symbol::from_val(&env, &last_event.1.get(0).unwrap()),
+            Symbol::new(&env, "KeeperPaid")
+        );
+        assert_eq!(u64::from_val(&env, &last_event.1.get(1).unwrap()), task_id);
+        let data: (Address, i128) = last_event.2.into_val(&env);
+        assert_eq!(data, (keeper, 10));
+    }
+
+    #[test]
+    fn test_execute_requires_keeper_auth() {
+        let (env, id) = setup();
+        let client = SoroTaskContractClient::new(&env, &id);
+        let keeper = Address::generate(&env);
+
+        let target = env.register_contract(None, MockTarget);
+        let task_id = register_funded(&env, &client, &base_config(&env, target));
+
+        set_timestamp(&env, 5_000);
+        client.execute(&keeper, &task_id);
+
+        assert_eq!(env.auths()[0].0, keeper);
+    }
+
+    /// No reward is paid when the resolver declines the run.
+    #[test]
+    fn test_execute_resolver_false_pays_nothing() {
+        let (env, id) = setup();
+        let client = SoroTaskContractClient::new(&env, &id);
+        let keeper = Address::generate(&env);
+
+        let target = env.register_contract(None, MockTarget);
+        let resolver = env.register_contract(None, resolver_false::MockResolverFalse);
+        let cfg = TaskParams {
+            resolver: Some(resolver),
+            ..base_config(&env, target)
+        };
+        let task_id = register_funded(&env, &client, &cfg);
+
+        set_timestamp(&env, 5_000);
+        client.execute(&keeper, &task_id);
+
+        assert_eq!(balance(&env, &client.get_token(), &keeper), 0);
+        assert_eq!(client.get_task(&task_id).unwrap().gas_balance, 1_000);
+    }
+
+    /// Execution is refused once the escrow cannot cover the fee.
+    #[test]
+    fn test_execute_insufficient_balance() {
+        let (env, id) = setup();
+        let client = SoroTaskContractClient::new(&env, &id);
+        let keeper = Address::generate(&env);
+
+        let target = env.register_contract(None, MockTarget);
+        let mut cfg = base_config(&env, target);
+        cfg.gas_balance = 5;
+        let task_id = register_funded(&env, &client, &cfg);
+
+        set_timestamp(&env, 5_000);
+        assert_eq!(
+            client.try_execute(&keeper, &task_id),
+            Err(Ok(Error::InsufficientBalance.into()))
+        );
+        assert_eq!(client.get_task(&task_id).unwrap().last_run, 0);
+    }
+
+    #[test]
+    fn test_register_negative_fee() {
+        let (env, id) = setup();
+        let client = SoroTaskContractClient::new(&env, &id);
+
+        let mut cfg = base_config(&env, Address::generate(&env));
+        cfg.fee = -1;
+        assert_eq!(
+            client.try_register(&cfg),
+            Err(Ok(Error::InvalidAmount.into()))
+        );
+    }
+
+    // ── Monitor ──────────────────────────────────────────────────────────────
+
+    /// Registers one task per interval against a shared mock target.
+    fn register_many(env: &Env, client: &SoroTaskContractClient, intervals: &[u64]) {
+        let target = env.register_contract(None, MockTarget);
+        for interval in intervals {
+            let mut cfg = base_config(env, target.clone());
+            cfg.schedule = Schedule::Interval(*interval);
+            register_funded(env, client, &cfg);
+        }
+    }
+
+    #[test]
+    fn test_monitor_returns_only_due_tasks() {
+        let (env, id) = setup();
+        let client = SoroTaskContractClient::new(&env, &id);
+        register_many(&env, &client, &[100, 500, 200, 1_000]);
+
+        set_timestamp(&env, 300);
+        let due = client.monitor(&1, &10);
+        assert_eq!(
+            due,
+            vec![
+                &env,
+                DueTask {
+                    task_id: 1,
+                    next_run_at: 100
+                },
+                DueTask {
+                    task_id: 3,
+                    next_run_at: 200
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_monitor_paginates() {
+        let (env, id) = setup();
+        let client = SoroTaskContractClient::new(&env, &id);
+        register_many(&env, &client, &[10, 10, 10, 10, 10]);
+        assert_eq!(client.task_count(), 5);
+
+        set_timestamp(&env, 100);
+        let page1 = client.monitor(&1, &2);
+        let page2 = client.monitor(&3, &2);
+        let page3 = client.monitor(&5, &2);
+        let page4 = client.monitor(&7, &2);
+
+        assert_eq!(page1.len(), 2);
+        assert_eq!(page1.get(0).unwrap().task_id, 1);
+        assert_eq!(page2.get(0).unwrap().task_id, 3);
+        assert_eq!(page3.len(), 1);
+        assert_eq!(page3.get(0).unwrap().task_id, 5);
+        assert!(page4.is_empty());
+    }
+
+    /// A start ID of 0 is treated as the first task.
+    #[test]
+    fn test_monitor_start_zero() {
+        let (env, id) = setup();
+        let client = SoroTaskContractClient::new(&env, &id);
+        register_many(&env, &client, &[10]);
+
+        set_timestamp(&env, 10);
+        assert_eq!(client.monitor(&0, &1).get(0).unwrap().task_id, 1);
+    }
+
+    /// Executed tasks drop out of the due set until their interval elapses.
+    #[test]
+    fn test_monitor_after_execute() {
+        let (env, id) = setup();
+        let client = SoroTaskContractClient::new(&env, &id);
+        let keeper = Address::generate(&env);
+        register_many(&env, &client, &[100, 100]);
+
+        set_timestamp(&env, 100);
+        client.execute(&keeper, &1);
+        let due = client.monitor(&1, &10);
+        assert_eq!(due.len(), 1);
+        assert_eq!(due.get(0).unwrap().task_id, 2);
+
+        set_timestamp(&env, 200);
+        assert_eq!(client.monitor(&1, &10).len(), 2);
+    }
+
+    // ── Cancellation ─────────────────────────────────────────────────────────
+
+    #[test]
+    fn test_cancel_task_refunds_creator() {
+        let (env, id) = setup();
+        let client = SoroTaskContractClient::new(&env, &id);
+        let token = client.get_token();
+
+        let cfg = base_config(&env, Address::generate(&env));
+        let task_id = register_funded(&env, &client, &cfg);
+
+        client.cancel_task(&task_id);
+        assert_eq!(env.auths()[0].0, cfg.creator);
+        let events = env.events().all();
+
+        assert!(client.get_task(&task_id).is_none());
+        assert_eq!(balance(&env, &token, &cfg.creator), 1_000);
+        assert_eq!(balance(&env, &token, &id), 0);
+
+        let last_event = events.last().unwrap();
+        assert_eq!(
+            Symbol::from_val(&env, &last_event.1.get(0).unwrap()),
+            Symbol::new(&env, "TaskCancelled")
+        );
+        let data: (Address, i128) = last_event.2.into_val(&env);
+        assert_eq!(data, (cfg.creator, 1_000));
+    }
+
+    /// Only what is left after keeper payouts is refunded.
+    #[test]
+    fn test_cancel_task_after_execution() {
+        let (env, id) = setup();
+        let client = SoroTaskContractClient::new(&env, &id);
+        let keeper = Address::generate(&env);
+        let token = client.get_token();
+
+        let target = env.register_contract(None, MockTarget);
+        let cfg = base_config(&env, target);
+        let task_id = register_funded(&env, &client, &cfg);
+
+        set_timestamp(&env, 5_000);
+        client.execute(&keeper, &task_id);
+        client.cancel_task(&task_id);
+
+        assert_eq!(balance(&env, &token, &cfg.creator), 990);
+        assert_eq!(balance(&env, &token, &keeper), 10);
+        assert_eq!(balance(&env, &token, &id), 0);
+    }
+
+    #[test]
+    fn test_execute_cancelled_task_fails() {
+        let (env, id) = setup();
+        let client = SoroTaskContractClient::new(&env, &id);
+        let keeper = Address::generate(&env);
+
+        let target = env.register_contract(None, MockTarget);
+        let task_id = register_funded(&env, &client, &base_config(&env, target));
+        client.cancel_task(&task_id);
+
+        set_timestamp(&env, 5_000);
+        assert_eq!(
+            client.try_execute(&keeper, &task_id),
+            Err(Ok(Error::TaskNotFound.into()))
+        );
+        assert_eq!(
+            client.try_cancel_task(&task_id),
+            Err(Ok(Error::TaskNotFound.into()))
+        );
+    }
+
+    #[test]
+    fn test_monitor_skips_cancelled_tasks() {
+        let (env, id) = setup();
+        let client = SoroTaskContractClient::new(&env, &id);
+        register_many(&env, &client, &[10, 10, 10]);
+        client.cancel_task(&2);
+
+        set_timestamp(&env, 10);
+        let due = client.monitor(&1, &10);
+        assert_eq!(due.len(), 2);
+        assert_eq!(due.get(1).unwrap().task_id, 3);
+    }
+
+    // ── Pause / resume ───────────────────────────────────────────────────────
+
+    /// Execution state is owned by the contract and starts from scratch.
+    #[test]
+    fn test_register_initializes_task_state() {
+        let (env, id) = setup();
+        let client = SoroTaskContractClient::new(&env, &id);
+        set_timestamp(&env, 50_000);
+
+        let cfg = base_config(&env, Address::generate(&env));
+        let task_id = register_funded(&env, &client, &cfg);
+
+        let stored = client.get_task(&task_id).unwrap();
+        assert_eq!(stored.status, TaskStatus::Active);
+        assert_eq!(stored.last_run, 0);
+        assert_eq!(stored.execution_count, 0);
+        assert!(stored.live_until > 0);
+    }
+
+    #[test]
+    fn test_pause_blocks_execute_and_monitor() {
+        let (env, id) = setup();
+        let client = SoroTaskContractClient::new(&env, &id);
+        let keeper = Address::generate(&env);
+
+        let target = env.register_contract(None, MockTarget);
+        let cfg = base_config(&env, target);
+        let task_id = register_funded(&env, &client, &cfg);
+
+        client.pause_task(&task_id);
+        assert_eq!(env.auths()[0].0, cfg.creator);
+        let events = env.events().all();
+        let last_event = events.last().unwrap();
+        assert_eq!(
+            Symbol::from_val(&env, &last_event.1.get(0).unwrap()),
+            Symbol::new(&env, "TaskPaused")
+        );
+
+        set_timestamp(&env, 5_000);
+        assert!(client.monitor(&1, &10).is_empty());
+        assert_eq!(
+            client.try_execute(&keeper, &task_id),
+            Err(Ok(Error::TaskPaused.into()))
+        );
+
+        let stored = client.get_task(&task_id).unwrap();
+        assert_eq!(stored.status, TaskStatus::Paused);
+        assert_eq!(stored.gas_balance, 1_000);
+        assert_eq!(stored.last_run, 0);
+    }
+
+    #[test]
+    fn test_resume_allows_execution() {
+        let (env, id) = setup();
+        let client = SoroTaskContractClient::new(&env, &id);
+        let keeper = Address::generate(&env);
+
+        let target = env.register_contract(None, MockTarget);
+        let task_id = register_funded(&env, &client, &base_config(&env, target));
+
+        client.pause_task(&task_id);
+        client.resume_task(&task_id);
+        let events = env.events().all();
+        let last_event = events.last().unwrap();
+        assert_eq!(
+            Symbol::from_val(&env, &last_event.1.get(0).unwrap()),
+            Symbol::new(&env, "TaskResumed")
+        );
+
+        set_timestamp(&env, 5_000);
+        assert_eq!(client.monitor(&1, &10).len(), 1);
+        client.execute(&keeper, &task_id);
+        assert_eq!(client.get_task(&task_id).unwrap().last_run, 5_000);
+    }
+
+    #[test]
+    fn test_pause_resume_invalid_transitions() {
+        let (env, id) = setup();
+        let client = SoroTaskContractClient::new(&env, &id);
+
+        let task_id = register_funded(&env, &client, &base_config(&env, Address::generate(&env)));
+
+        assert_eq!(
+            client.try_resume_task(&task_id),
+            Err(Ok(Error::TaskNotPaused.into()))
+        );
+        client.pause_task(&task_id);
+        assert_eq!(
+            client.try_pause_task(&task_id),
+            Err(Ok(Error::TaskPaused.into()))
+        );
+    }
+
+    // ── Update ───────────────────────────────────────────────────────────────
+
+    fn empty_patch(env: &Env) -> TaskPatch {
+        TaskPatch {
+            target: None,
+            function: None,
+            args: None,
+            resolver: None,
+            clear_resolver: false,
+            resolver_mode: ResolverMode::Condition,
+            resolver_function: None,
+            resolver_args: Vec::new(env),
+            conditions: None,
+            condition_tree: None,
+            interval: None,
+            bonded_only: None,
+        }
+    }
+
+    #[test]
+    fn test_update_task_replaces_selected_fields() {
+        let (env, id) = setup();
+        let client = SoroTaskContractClient::new(&env, &id);
+        let keeper = Address::generate(&env);
+
+        let target = env.register_contract(None, MockTarget);
+        let cfg = base_config(&env, target.clone());
+        let task_id = register_funded(&env, &client, &cfg);
+
+        let args: Vec<Val> = vec![&env, 2_i64.into_val(&env), 3_i64.into_val(&env)];
+        let patch = TaskPatch {
+            function: Some(Symbol::new(&env, "add")),
+            args: Some(args.clone()),
+            interval: Some(60),
+            ..empty_patch(&env)
+        };
+        client.update_task(&task_id, &patch);
+        assert_eq!(env.auths()[0].0, cfg.creator);
+
+        let events = env.events().all();
+        let last_event = events.last().unwrap();
+        assert_eq!(
+            Symbol::from_val(&env, &last_event.1.get(0).unwrap()),
+            Symbol::new(&env, "TaskUpdated")
+        );
+        let changed: Vec<Symbol> = last_event.2.into_val(&env);
+        assert_eq!(
+            changed,
+            vec![
+                &env,
+                Symbol::new(&env, "function"),
+                Symbol::new(&env, "args"),
+                Symbol::new(&env, "interval"),
+            ]
+        );
+
+        let stored = client.get_task(&task_id).unwrap();
+        assert_eq!(stored.target, target);
+        assert_eq!(stored.function, Symbol::new(&env, "add"));
+        assert_eq!(stored.args, args);
+        assert_eq!(stored.schedule, Schedule::Interval(60));
+        assert_eq!(stored.gas_balance, 1_000);
+
+        // The patched call must still succeed against the target.
+        set_timestamp(&env, 60);
+        client.execute(&keeper, &task_id);
+        assert_eq!(client.get_task(&task_id).unwrap().last_run, 60);
+    }
+
+    #[test]
+    fn test_update_task_sets_and_clears_resolver() {
+        let (env, id) = setup();
+        let client = SoroTaskContractClient::new(&env, &id);
+
+        let task_id = register_funded(&env, &client, &base_config(&env, Address::generate(&env)));
+        let resolver = Address::generate(&env);
+
+        client.update_task(
+            &task_id,
+            &TaskPatch {
+                resolver: Some(resolver.clone()),
+                ..empty_patch(&env)
+            },
+        );
+        assert_eq!(client.get_task(&task_id).unwrap().resolver, Some(resolver));
+
+        client.update_task(
+            &task_id,
+            &TaskPatch {
+                clear_resolver: true,
+                ..empty_patch(&env)
+            },
+        );
+        assert_eq!(client.get_task(&task_id).unwrap().resolver, None);
+    }
+
+    #[test]
+    fn test_update_task_invalid_interval() {
+        let (env, id) = setup();
+        let client = SoroTaskContractClient::new(&env, &id);
+
+        let task_id = register_funded(&env, &client, &base_config(&env, Address::generate(&env)));
+        let patch = TaskPatch {
+            interval: Some(0),
+            ..empty_patch(&env)
+        };
+        assert_eq!(
+            client.try_update_task(&task_id, &patch),
+            Err(Ok(Error::InvalidInterval.into()))
+        );
+        assert_eq!(
+            client.get_task(&task_id).unwrap().schedule,
+            Schedule::Interval(3_600)
+        );
+    }
+
+    #[test]
+    fn test_update_missing_task() {
+        let (env, id) = setup();
+        let client = SoroTaskContractClient::new(&env, &id);
+        assert_eq!(
+            client.try_update_task(&7, &empty_patch(&env)),
+            Err(Ok(Error::TaskNotFound.into()))
+        );
+    }
+
+    // ── Error codes ──────────────────────────────────────────────────────────
+
+    /// Error codes are matched numerically by clients and must stay stable.
+    #[test]
+    fn test_error_codes_are_stable() {
+        let codes = [
+            (Error::InvalidInterval, 1),
+            (Error::AlreadyInitialized, 2),
+            (Error::NotInitialized, 3),
+            (Error::TaskNotFound, 4),
+            (Error::InvalidAmount, 5),
+            (Error::InsufficientBalance, 6),
+            (Error::TaskPaused, 7),
+            (Error::TaskNotPaused, 8),
+            (Error::NotDue, 9),
+            (Error::ResolverRejected, 10),
+            (Error::InvalidTtlConfig, 11),
+            (Error::InvalidCondition, 12),
+            (Error::InvalidSchedule, 13),
+            (Error::TaskFinished, 14),
+            (Error::InvalidTimeWindow, 15),
+            (Error::InvalidExecutionLimit, 16),
+            (Error::KeeperNotRegistered, 17),
+            (Error::KeeperNotBonded, 18),
+            (Error::KeeperUnbonding, 19),
+            (Error::StakeLocked, 20),
+            (Error::InsufficientStake, 21),
+            (Error::TaskNotAssigned, 22),
+            (Error::NotMissed, 23),
+            (Error::TaskAlreadyAssigned, 24),
+            (Error::KeeperNotAllowed, 25),
+            (Error::NothingToClaim, 26),
+            (Error::FeeQuoteTooHigh, 27),
+            (Error::ProtectedAddress, 28),
+            (Error::KeeperRegistryFull, 29),
+        ];
+        for (error, code) in codes {
+            assert_eq!(
+                soroban_sdk::Error::from(error),
+                soroban_sdk::Error::from_contract_error(code)
+            );
+        }
+    }
+
+    /// Auth checks have no contract code; they fail with the host's error.
+    #[test]
+    fn test_missing_auth_is_host_error() {
+        let (env, id) = setup();
+        let client = SoroTaskContractClient::new(&env, &id);
+
+        let task_id = register_funded(&env, &client, &base_config(&env, Address::generate(&env)));
+        env.set_auths(&[]);
+        let Err(Ok(error)) = client.try_pause_task(&task_id) else {
+            panic!("pause_task succeeded without auth");
+        };
+        assert!(!error.is_type(soroban_sdk::xdr::ScErrorType::Contract));
+    }
+
+    #[test]
+    fn test_execute_missing_task_error() {
+        let (env, id) = setup();
+        let client = SoroTaskContractClient::new(&env, &id);
+        let keeper = Address::generate(&env);
+        assert_eq!(
+            client.try_execute(&keeper, &1),
+            Err(Ok(Error::TaskNotFound.into()))
+        );
+    }
+
+    /// A trapping resolver surfaces as ResolverFailed, not a host error.
+    #[test]
+    fn test_execute_resolver_failure_outcome() {
+        let (env, id) = setup();
+        let client = SoroTaskContractClient::new(&env, &id);
+        let keeper = Address::generate(&env);
+
+        let target = env.register_contract(None, MockTarget);
+        let resolver = env.register_contract(None, resolver_panic::MockResolverPanic);
+        let cfg = TaskParams {
+            resolver: Some(resolver),
+            ..base_config(&env, target)
+        };
+        let task_id = register_funded(&env, &client, &cfg);
+
+        set_timestamp(&env, 5_000);
+        assert_eq!(
+            client.execute(&keeper, &task_id),
+            ExecutionOutcome::ResolverFailed
+        );
+        assert_eq!(last_event_name(&env), Symbol::new(&env, "ResolverFailed"));
+        assert_eq!(client.get_task(&task_id).unwrap().last_run, 0);
+    }
+
+    /// NotDue is reported before balance and resolver checks.
+    #[test]
+    fn test_execute_not_due_outcome() {
+        let (env, id) = setup();
+        let client = SoroTaskContractClient::new(&env, &id);
+        let keeper = Address::generate(&env);
+
+        let mut cfg = base_config(&env, Address::generate(&env));
+        cfg.gas_balance = 0;
+        let task_id = register_funded(&env, &client, &cfg);
+
+        set_timestamp(&env, 3_599);
+        assert_eq!(
+            client.execute(&keeper, &task_id),
+            ExecutionOutcome::NotDue(3_600)
+        );
+        let events = env.events().all();
+        let last_event = events.last().unwrap();
+        assert_eq!(
+            Symbol::from_val(&env, &last_event.1.get(0).unwrap()),
+            Symbol::new(&env, "TaskNotDue")
+        );
+        assert_eq!(u64::from_val(&env, &last_event.2), 3_600);
+    }
+
+    // ── Execution outcomes ───────────────────────────────────────────────────
+
+    #[test]
+    fn test_execute_outcome_executed() {
+        let (env, id) = setup();
+        let client = SoroTaskContractClient::new(&env, &id);
+        let keeper = Address::generate(&env);
+
+        let target = env.register_contract(None, MockTarget);
+        let mut cfg = base_config(&env, target);
+        cfg.fee = 0;
+        let task_id = register_funded(&env, &client, &cfg);
+
+        set_timestamp(&env, 4_000);
+        assert_eq!(
+            client.execute(&keeper, &task_id),
+            ExecutionOutcome::Executed
+        );
+        let events = env.events().all();
+        let last_event = events.last().unwrap();
+        assert_eq!(
+            Symbol::from_val(&env, &last_event.1.get(0).unwrap()),
+            Symbol::new(&env, "TaskExecuted")
+        );
+        let data: (Address, u64) = last_event.2.into_val(&env);
+        assert_eq!(data, (keeper, 4_000));
+    }
+
+    #[test]
+    fn test_execute_outcome_resolver_declined_event() {
+        let (env, id) = setup();
+        let client = SoroTaskContractClient::new(&env, &id);
+        let keeper = Address::generate(&env);
+
+        let target = env.register_contract(None, MockTarget);
+        let resolver = env.register_contract(None, resolver_false::MockResolverFalse);
+        let cfg = TaskParams {
+            resolver: Some(resolver),
+            ..base_config(&env, target)
+        };
+        let task_id = register_funded(&env, &client, &cfg);
+
+        set_timestamp(&env, 4_000);
+        client.execute(&keeper, &task_id);
+        assert_eq!(last_event_name(&env), Symbol::new(&env, "ResolverDeclined"));
+    }
+
+    // ── Batch execution ──────────────────────────────────────────────────────
+
+    #[test]
+    fn test_execute_batch_mixed_outcomes() {
+        let (env, id) = setup();
+        let client = SoroTaskContractClient::new(&env, &id);
+        let keeper = Address::generate(&env);
+        let token = client.get_token();
+
+        let target = env.register_contract(None, MockTarget);
+        let ok_id = register_funded(&env, &client, &base_config(&env, target.clone()));
+
+        let mut failing = base_config(&env, target.clone());
+        failing.function = Symbol::new(&env, "fail");
+        let failing_id = register_funded(&env, &client, &failing);
+
+        let mut later = base_config(&env, target.clone());
+        later.schedule = Schedule::Interval(10_000);
+        let later_id = register_funded(&env, &client, &later);
+
+        let paused_id = register_funded(&env, &client, &base_config(&env, target.clone()));
+        client.pause_task(&paused_id);
+
+        let ok2_id = register_funded(&env, &client, &base_config(&env, target));
+
+        set_timestamp(&env, 5_000);
+        let result = client.execute_batch(
+            &keeper,
+            &vec![&env, ok_id, failing_id, later_id, paused_id, 99, ok2_id],
+        );
+
+        assert_eq!(
+            result.outcomes,
+            vec![
+                &env,
+                ExecutionOutcome::Executed,
+                ExecutionOutcome::TargetFailed,
+                ExecutionOutcome::NotDue(10_000),
+                ExecutionOutcome::Rejected(Error::TaskPaused as u32),
+                ExecutionOutcome::Rejected(Error::TaskNotFound as u32),
+                ExecutionOutcome::Executed,
+            ]
+        );
+        assert_eq!(result.total_reward, 20);
+        assert_eq!(balance(&env, &token, &keeper), 20);
+
+        assert_eq!(client.get_task(&ok_id).unwrap().last_run, 5_000);
+        assert_eq!(client.get_task(&ok2_id).unwrap().last_run, 5_000);
+        let failed = client.get_task(&failing_id).unwrap();
+        assert_eq!(failed.last_run, 0);
+        assert_eq!(failed.gas_balance, 1_000);
+    }
+
+    /// A task listed twice only runs once per interval.
+    #[test]
+    fn test_execute_batch_duplicate_ids() {
+        let (env, id) = setup();
+        let client = SoroTaskContractClient::new(&env, &id);
+        let keeper = Address::generate(&env);
+
+        let target = env.register_contract(None, MockTarget);
+        let task_id = register_funded(&env, &client, &base_config(&env, target));
+
+        set_timestamp(&env, 5_000);
+        let result = client.execute_batch(&keeper, &vec![&env, task_id, task_id]);
+        assert_eq!(
+            result.outcomes,
+            vec![
+                &env,
+                ExecutionOutcome::Executed,
+                ExecutionOutcome::NotDue(8_600),
+            ]
+        );
+        assert_eq!(result.total_reward, 10);
+    }
+
+    #[test]
+    fn test_execute_batch_insufficient_balance_rejected() {
+        let (env, id) = setup();
+        let client = SoroTaskContractClient::new(&env, &id);
+        let keeper = Address::generate(&env);
+
+        let target = env.register_contract(None, MockTarget);
+        let mut cfg = base_config(&env, target);
+        cfg.gas_balance = 0;
+        let task_id = register_funded(&env, &client, &cfg);
+
+        set_timestamp(&env, 5_000);
+        let result = client.execute_batch(&keeper, &vec![&env, task_id]);
+        assert_eq!(
+            result.outcomes,
+            vec![
+                &env,
+                ExecutionOutcome::Rejected(Error::InsufficientBalance as u32)
+            ]
+        );
+        assert_eq!(result.total_reward, 0);
+        assert_eq!(balance(&env, &client.get_token(), &keeper), 0);
+    }
+
+    /// Single `execute` keeps its atomic semantics: a failing target reverts.
+    #[test]
+    fn test_execute_failing_target_reverts() {
+        let (env, id) = setup();
+        let client = SoroTaskContractClient::new(&env, &id);
+        let keeper = Address::generate(&env);
+
+        let target = env.register_contract(None, MockTarget);
+        let mut cfg = base_config(&env, target);
+        cfg.function = Symbol::new(&env, "fail");
+        let task_id = register_funded(&env, &client, &cfg);
+
+        set_timestamp(&env, 5_000);
+        assert!(client.try_execute(&keeper, &task_id).is_err());
+        assert_eq!(client.get_task(&task_id).unwrap().gas_balance, 1_000);
+    }
+
+    // ── Storage TTL ──────────────────────────────────────────────────────────
+
+    /// Installs a short TTL policy extending entries to 20_000 ledgers.
+    ///
+    /// Entries created by the test harness (token balances, contract
+    /// instances) start with a TTL of 4_096 ledgers, so tests that touch the
+    /// token must stay below that sequence.
+    fn short_ttl(client: &SoroTaskContractClient, threshold: u32, bump_fee: i128) {
+        client.set_ttl_config(&TtlConfig {
+            threshold,
+            extend_to: 20_000,
+            bump_fee,
+        });
+    }
+
+    #[test]
+    fn test_register_extends_task_ttl() {
+        let (env, id) = setup();
+        let client = SoroTaskContractClient::new(&env, &id);
+
+        set_sequence(&env, 100);
+        let task_id = register_funded(&env, &client, &base_config(&env, Address::generate(&env)));
+
+        let ttl = client.get_ttl_config();
+        assert_eq!(ttl.extend_to, 120 * DAY_IN_LEDGERS);
+        assert_eq!(task_ttl(&env, &id, task_id), ttl.extend_to);
+        assert_eq!(
+            client.get_task(&task_id).unwrap().live_until,
+            100 + ttl.extend_to
+        );
+    }
+
+    /// Without a bump the entry is archived once the ledger passes its TTL,
+    /// while a bumped sibling (which also extends the instance) stays live.
+    #[test]
+    #[should_panic(expected = "Error(Storage, InternalError)")]
+    fn test_task_archived_without_bump() {
+        let (env, id) = setup();
+        let client = SoroTaskContractClient::new(&env, &id);
+        let caller = Address::generate(&env);
+        short_ttl(&client, 10_000, 0);
+
+        let cfg = base_config(&env, Address::generate(&env));
+        let stale_id = register_funded(&env, &client, &cfg);
+        let bumped_id = register_funded(&env, &client, &cfg);
+        assert_eq!(client.get_task(&stale_id).unwrap().live_until, 20_000);
+
+        set_sequence(&env, 15_000);
+        assert!(client.bump_task(&caller, &bumped_id));
+
+        set_sequence(&env, 20_001);
+        assert!(client.get_task(&bumped_id).is_some());
+        client.get_task(&stale_id);
+    }
+
+    #[test]
+    fn test_bump_task_keeps_task_live() {
+        let (env, id) = setup();
+        let client = SoroTaskContractClient::new(&env, &id);
+        let caller = Address::generate(&env);
+        short_ttl(&client, 10_000, 0);
+
+        let task_id = register_funded(&env, &client, &base_config(&env, Address::generate(&env)));
+
+        // Within the threshold window: the bump extends the entry.
+        set_sequence(&env, 19_500);
+        assert!(client.bump_task(&caller, &task_id));
+        assert_eq!(task_ttl(&env, &id, task_id), 20_000);
+
+        set_sequence(&env, 20_001);
+        assert_eq!(
+            client.get_task(&task_id).unwrap().live_until,
+            19_500 + 20_000
+        );
+    }
+
+    /// A bump outside the threshold window does nothing and pays nothing.
+    #[test]
+    fn test_bump_task_not_needed() {
+        let (env, id) = setup();
+        let client = SoroTaskContractClient::new(&env, &id);
+        let caller = Address::generate(&env);
+        short_ttl(&client, 10_000, 25);
+
+        let task_id = register_funded(&env, &client, &base_config(&env, Address::generate(&env)));
+
+        assert!(!client.bump_task(&caller, &task_id));
+        assert_eq!(balance(&env, &client.get_token(), &caller), 0);
+        assert_eq!(client.get_task(&task_id).unwrap().gas_balance, 1_000);
+    }
+
+    /// The bump fee is reimbursed from the task's gas balance.
+    #[test]
+    fn test_bump_task_pays_fee_from_gas_balance() {
+        let (env, id) = setup();
+        let client = SoroTaskContractClient::new(&env, &id);
+        let caller = Address::generate(&env);
+        short_ttl(&client, 19_000, 25);
+
+        let task_id = register_funded(&env, &client, &base_config(&env, Address::generate(&env)));
+
+        set_sequence(&env, 1_500);
+        assert!(client.bump_task(&caller, &task_id));
+        assert_eq!(balance(&env, &client.get_token(), &caller), 25);
+
+        let stored = client.get_task(&task_id).unwrap();
+        assert_eq!(stored.gas_balance, 975);
+        assert_eq!(stored.live_until, 21_500);
+
+        // Immediately bumping again is a no-op.
+        assert!(!client.bump_task(&caller, &task_id));
+        assert_eq!(balance(&env, &client.get_token(), &caller), 25);
+    }
+
+    /// Executing a task near expiry extends it as well.
+    #[test]
+    fn test_execute_extends_task_ttl() {
+        let (env, id) = setup();
+        let client = SoroTaskContractClient::new(&env, &id);
+        let keeper = Address::generate(&env);
+        short_ttl(&client, 19_000, 0);
+
+        let target = env.register_contract(None, MockTarget);
+        let task_id = register_funded(&env, &client, &base_config(&env, target));
+
+        set_sequence(&env, 1_500);
+        set_timestamp(&env, 5_000);
+        client.execute(&keeper, &task_id);
+        assert_eq!(task_ttl(&env, &id, task_id), 20_000);
+
+        set_sequence(&env, 21_000);
+        assert_eq!(client.get_task(&task_id).unwrap().last_run, 5_000);
+    }
+
+    #[test]
+    fn test_set_ttl_config_validation() {
+        let (env, id) = setup();
+        let client = SoroTaskContractClient::new(&env, &id);
+
+        let inverted = TtlConfig {
+            threshold: 10,
+            extend_to: 5,
+            bump_fee: 0,
+        };
+        assert_eq!(
+            client.try_set_ttl_config(&inverted),
+            Err(Ok(Error::InvalidTtlConfig.into()))
+        );
+        let negative_fee = TtlConfig {
+            threshold: 5,
+            extend_to: 10,
+            bump_fee: -1,
+        };
+        assert_eq!(
+            client.try_set_ttl_config(&negative_fee),
+            Err(Ok(Error::InvalidAmount.into()))
+        );
+    }
+
+    // ── Payload resolvers ────────────────────────────────────────────────────
+
+    fn recorder_config(env: &Env, target: Address, resolver: Address) -> TaskParams {
+        TaskParams {
+            function: Symbol::new(env, "record"),
+            args: vec![env, 2_i64.into_val(env), 3_i64.into_val(env)],
+            resolver: Some(resolver),
+            resolver_mode: ResolverMode::Payload,
+            ..base_config(env, target)
+        }
+    }
+
+    /// The target receives the resolver-computed args, not the static ones.
+    #[test]
+    fn test_execute_payload_resolver_forwards_computed_args() {
+        let (env, id) = setup();
+        let client = SoroTaskContractClient::new(&env, &id);
+        let keeper = Address::generate(&env);
+
+        let target = env.register_contract(None, MockTarget);
+        let resolver = env.register_contract(None, resolver_payload::MockPayloadResolver);
+        let cfg = recorder_config(&env, target.clone(), resolver);
+        let task_id = register_funded(&env, &client, &cfg);
+
+        set_timestamp(&env, 5_000);
+        assert_eq!(
+            client.execute(&keeper, &task_id),
+            ExecutionOutcome::Executed
+        );
+        assert_eq!(MockTargetClient::new(&env, &target).recorded(), Some(10));
+    }
+
+    #[test]
+    fn test_execute_payload_resolver_declined() {
+        let (env, id) = setup();
+        let client = SoroTaskContractClient::new(&env, &id);
+        let keeper = Address::generate(&env);
+
+        let target = env.register_contract(None, MockTarget);
+        let resolver =
+            env.register_contract(None, resolver_payload_false::MockPayloadResolverFalse);
+        let task_id = register_funded(
+            &env,
+            &client,
+            &recorder_config(&env, target.clone(), resolver),
+        );
+
+        set_timestamp(&env, 5_000);
+        assert_eq!(
+            client.execute(&keeper, &task_id),
+            ExecutionOutcome::ResolverDeclined
+        );
+        assert_eq!(MockTargetClient::new(&env, &target).recorded(), None);
+    }
+
+    /// A condition-only resolver used in payload mode fails closed.
+    #[test]
+    fn test_execute_payload_mode_with_condition_resolver_fails() {
+        let (env, id) = setup();
+        let client = SoroTaskContractClient::new(&env, &id);
+        let keeper = Address::generate(&env);
+
+        let target = env.register_contract(None, MockTarget);
+        let resolver = env.register_contract(None, resolver_true::MockResolverTrue);
+        let task_id = register_funded(
+            &env,
+            &client,
+            &recorder_config(&env, target.clone(), resolver),
+        );
+
+        set_timestamp(&env, 5_000);
+        assert_eq!(
+            client.execute(&keeper, &task_id),
+            ExecutionOutcome::ResolverFailed
+        );
+        assert_eq!(MockTargetClient::new(&env, &target).recorded(), None);
+    }
+
+    /// Condition mode keeps forwarding the static args.
+    #[test]
+    fn test_execute_condition_resolver_forwards_static_args() {
+        let (env, id) = setup();
+        let client = SoroTaskContractClient::new(&env, &id);
+        let keeper = Address::generate(&env);
+
+        let target = env.register_contract(None, MockTarget);
+        let resolver = env.register_contract(None, resolver_true::MockResolverTrue);
+        let cfg = TaskParams {
+            resolver_mode: ResolverMode::Condition,
+            ..recorder_config(&env, target.clone(), resolver)
+        };
+        let task_id = register_funded(&env, &client, &cfg);
+
+        set_timestamp(&env, 5_000);
+        client.execute(&keeper, &task_id);
+        assert_eq!(MockTargetClient::new(&env, &target).recorded(), Some(5));
+    }
+
+    #[test]
+    fn test_update_task_switches_resolver_mode() {
+        let (env, id) = setup();
+        let client = SoroTaskContractClient::new(&env, &id);
+
+        let task_id = register_funded(&env, &client, &base_config(&env, Address::generate(&env)));
+        let resolver = Address::generate(&env);
+        client.update_task(
+            &task_id,
+            &TaskPatch {
+                resolver: Some(resolver.clone()),
+                resolver_mode: ResolverMode::Payload,
+                ..empty_patch(&env)
+            },
+        );
+
+        let stored = client.get_task(&task_id).unwrap();
+        assert_eq!(stored.resolver, Some(resolver));
+        assert_eq!(stored.resolver_mode, ResolverMode::Payload);
+    }
+
+    // ── Custom resolver functions ────────────────────────────────────────────
+
+    fn oracle_config(
+        env: &Env,
+        target: Address,
+        function: &str,
+        resolver_args: Vec<Val>,
+    ) -> TaskParams {
+        let oracle = env.register_contract(None, oracle::MockOracle);
+        TaskParams {
+            function: Symbol::new(env, "record"),
+            args: vec![env, 1_i64.into_val(env), 1_i64.into_val(env)],
+            resolver: Some(oracle),
+            resolver_function: Some(Symbol::new(env, function)),
+            resolver_args,
+            ..base_config(env, target)
+        }
+    }
+
+    /// A view function is called with `resolver_args` spread positionally.
+    #[test]
+    fn test_execute_custom_resolver_function() {
+        let (env, id) = setup();
+        let client = SoroTaskContractClient::new(&env, &id);
+        let keeper = Address::generate(&env);
+        let target = env.register_contract(None, MockTarget);
+
+        let in_range = oracle_config(
+            &env,
+            target.clone(),
+            "price_between",
+            vec![&env, 90_i64.into_val(&env), 110_i64.into_val(&env)],
+        );
+        let out_of_range = oracle_config(
+            &env,
+            target.clone(),
+            "price_between",
+            vec![&env, 0_i64.into_val(&env), 50_i64.into_val(&env)],
+        );
+        let in_id = register_funded(&env, &client, &in_range);
+        let out_id = register_funded(&env, &client, &out_of_range);
+
+        set_timestamp(&env, 5_000);
+        assert_eq!(
+            client.execute(&keeper, &out_id),
+            ExecutionOutcome::ResolverDeclined
+        );
+        assert_eq!(client.execute(&keeper, &in_id), ExecutionOutcome::Executed);
+        assert_eq!(MockTargetClient::new(&env, &target).recorded(), Some(2));
+    }
+
+    /// A function with the wrong return type or arity fails closed.
+    #[test]
+    fn test_execute_custom_resolver_function_mismatch() {
+        let (env, id) = setup();
+        let client = SoroTaskContractClient::new(&env, &id);
+        let keeper = Address::generate(&env);
+        let target = env.register_contract(None, MockTarget);
+
+        // `price` returns i64, not bool.
+        let wrong_type = oracle_config(&env, target.clone(), "price", Vec::new(&env));
+        // `price_between` takes two arguments.
+        let wrong_arity = oracle_config(
+            &env,
+            target.clone(),
+            "price_between",
+            vec![&env, 90_i64.into_val(&env)],
+        );
+        let missing = oracle_config(&env, target, "no_such_fn", Vec::new(&env));
+
+        set_timestamp(&env, 5_000);
+        for cfg in [wrong_type, wrong_arity, missing] {
+            let task_id = register_funded(&env, &client, &cfg);
+            assert_eq!(
+                client.execute(&keeper, &task_id),
+                ExecutionOutcome::ResolverFailed
+            );
+        }
+    }
+
+    #[test]
+    fn test_execute_custom_payload_resolver_function() {
+        let (env, id) = setup();
+        let client = SoroTaskContractClient::new(&env, &id);
+        let keeper = Address::generate(&env);
+        let target = env.register_contract(None, MockTarget);
+
+        let cfg = TaskParams {
+            resolver_mode: ResolverMode::Payload,
+            ..oracle_config(
+                &env,
+                target.clone(),
+                "quote",
+                vec![&env, 7_i64.into_val(&env)],
+            )
+        };
+        let task_id = register_funded(&env, &client, &cfg);
+
+        set_timestamp(&env, 5_000);
+        client.execute(&keeper, &task_id);
+        assert_eq!(MockTargetClient::new(&env, &target).recorded(), Some(107));
+    }
+
+    #[test]
+    fn test_update_task_sets_resolver_function() {
+        let (env, id) = setup();
+        let client = SoroTaskContractClient::new(&env, &id);
+
+        let task_id = register_funded(&env, &client, &base_config(&env, Address::generate(&env)));
+        let resolver_args: Vec<Val> = vec![&env, 1_i64.into_val(&env)];
+        client.update_task(
+            &task_id,
+            &TaskPatch {
+                resolver: Some(Address::generate(&env)),
+                resolver_function: Some(Symbol::new(&env, "is_ready")),
+                resolver_args: resolver_args.clone(),
+                ..empty_patch(&env)
+            },
+        );
+
+        let stored = client.get_task(&task_id).unwrap();
+        assert_eq!(
+            stored.resolver_function,
+            Some(Symbol::new(&env, "is_ready"))
+        );
+        assert_eq!(stored.resolver_args, resolver_args);
+    }
+
+    // ── Composite conditions ─────────────────────────────────────────────────
+
+    fn clause(env: &Env, resolver: &Address, function: &str) -> ResolverClause {
+        ResolverClause {
+            resolver: resolver.clone(),
+            function: Symbol::new(env, function),
+            args: Vec::new(env),
+        }
+    }
+
+    fn counted_clause(env: &Env, resolver: &Address, value: bool) -> ResolverClause {
+        ResolverClause {
+            args: vec![env, value.into_val(env)],
+            ..clause(env, resolver, "counted")
+        }
+    }
+
+    fn composite_config(
+        env: &Env,
+        target: Address,
+        conditions: Vec<ResolverClause>,
+        condition_tree: Vec<ConditionNode>,
+    ) -> TaskParams {
+        TaskParams {
+            conditions,
+            condition_tree,
+            ..base_config(env, target)
+        }
+    }
+
+    #[test]
+    fn test_composite_and_or_not() {
+        let (env, id) = setup();
+        let client = SoroTaskContractClient::new(&env, &id);
+        let keeper = Address::generate(&env);
+        let target = env.register_contract(None, MockTarget);
+        let mock = env.register_contract(None, clauses::MockClauses);
+
+        let conditions = vec![&env, clause(&env, &mock, "yes"), clause(&env, &mock, "no")];
+        let cases = [
+            // yes AND no
+            (
+                vec![
+                    &env,
+                    ConditionNode::And(vec![&env, 1, 2]),
+                    ConditionNode::Clause(0),
+                    ConditionNode::Clause(1),
+                ],
+                ExecutionOutcome::ResolverDeclined,
+            ),
+            // no OR yes
+            (
+                vec![
+                    &env,
+                    ConditionNode::Or(vec![&env, 1, 2]),
+                    ConditionNode::Clause(1),
+                    ConditionNode::Clause(0),
+                ],
+                ExecutionOutcome::Executed,
+            ),
+            // NOT no
+            (
+                vec![&env, ConditionNode::Not(1), ConditionNode::Clause(1)],
+                ExecutionOutcome::Executed,
+            ),
+            // yes AND NOT (no OR no)
+            (
+                vec![
+                    &env,
+                    ConditionNode::And(vec![&env, 1, 2]),
+                    ConditionNode::Clause(0),
+                    ConditionNode::Not(3),
+                    ConditionNode::Or(vec![&env, 4, 5]),
+                    ConditionNode::Clause(1),
+                    ConditionNode::Clause(1),
+                ],
+                ExecutionOutcome::Executed,
+            ),
+        ];
+
+        set_timestamp(&env, 5_000);
+        for (tree, expected) in cases {
+            let cfg = composite_config(&env, target.clone(), conditions.clone(), tree);
+            let task_id = register_funded(&env, &client, &cfg);
+            assert_eq!(client.execute(&keeper, &task_id), expected);
+        }
+    }
+
+    /// `And` stops at the first false child and `Or` at the first true one.
+    #[test]
+    fn test_composite_short_circuits() {
+        let (env, id) = setup();
+        let client = SoroTaskContractClient::new(&env, &id);
+        let keeper = Address::generate(&env);
+        let target = env.register_contract(None, MockTarget);
+        let mock = env.register_contract(None, clauses::MockClauses);
+        let mock_client = clauses::MockClausesClient::new(&env, &mock);
+
+        let conditions = vec![
+            &env,
+            counted_clause(&env, &mock, false),
+            counted_clause(&env, &mock, true),
+        ];
+        let and_tree = vec![
+            &env,
+            ConditionNode::And(vec![&env, 1, 2]),
+            ConditionNode::Clause(0),
+            ConditionNode::Clause(1),
+        ];
+        let or_tree = vec![
+            &env,
+            ConditionNode::Or(vec![&env, 1, 2]),
+            ConditionNode::Clause(1),
+            ConditionNode::Clause(0),
+        ];
+
+        set_timestamp(&env, 5_000);
+        let and_id = register_funded(
+            &env,
+            &client,
+            &composite_config(&env, target.clone(), conditions.clone(), and_tree),
+        );
+        client.execute(&keeper, &and_id);
+        assert_eq!(mock_client.calls(), 1);
+
+        let or_id = register_funded(
+            &env,
+            &client,
+            &composite_config(&env, target, conditions, or_tree),
+        );
+        client.execute(&keeper, &or_id);
+        assert_eq!(mock_client.calls(), 2);
+    }
+
+    /// A failing clause fails the whole tree closed, even under `Not`.
+    #[test]
+    fn test_composite_failing_clause_fails_closed() {
+        let (env, id) = setup();
+        let client = SoroTaskContractClient::new(&env, &id);
+        let keeper = Address::generate(&env);
+        let target = env.register_contract(None, MockTarget);
+        let mock = env.register_contract(None, clauses::MockClauses);
+
+        let conditions = vec![&env, clause(&env, &mock, "boom")];
+        let tree = vec![&env, ConditionNode::Not(1), ConditionNode::Clause(0)];
+        let task_id = register_funded(
+            &env,
+            &client,
+            &composite_config(&env, target, conditions, tree),
+        );
+
+        set_timestamp(&env, 5_000);
+        assert_eq!(
+            client.execute(&keeper, &task_id),
+            ExecutionOutcome::ResolverFailed
+        );
+        assert_eq!(client.get_task(&task_id).unwrap().last_run, 0);
+    }
+
+    /// The single resolver still applies after a true condition tree.
+    #[test]
+    fn test_composite_combined_with_resolver() {
+        let (env, id) = setup();
+        let client = SoroTaskContractClient::new(&env, &id);
+        let keeper = Address::generate(&env);
+        let target = env.register_contract(None, MockTarget);
+        let mock = env.register_contract(None, clauses::MockClauses);
+
+        let cfg = TaskParams {
+            resolver: Some(env.register_contract(None, resolver_false::MockResolverFalse)),
+            ..composite_config(
+                &env,
+                target,
+                vec![&env, clause(&env, &mock, "yes")],
+                vec![&env, ConditionNode::Clause(0)],
+            )
+        };
+        let task_id = register_funded(&env, &client, &cfg);
+
+        set_timestamp(&env, 5_000);
+        assert_eq!(
+            client.execute(&keeper, &task_id),
+            ExecutionOutcome::ResolverDeclined
+        );
+    }
+
+    #[test]
+    fn test_register_invalid_condition_tree() {
+        let (env, id) = setup();
+        let client = SoroTaskContractClient::new(&env, &id);
+        let mock = Address::generate(&env);
+        let conditions = vec![&env, clause(&env, &mock, "yes")];
+
+        let mut too_large = Vec::new(&env);
+        for _ in 0..=MAX_CONDITION_NODES {
+            too_large.push_back(ConditionNode::Clause(0));
+        }
+        let invalid = [
+            // clause index out of range
+            vec![&env, ConditionNode::Clause(1)],
+            // child refers back to its parent
+            vec![&env, ConditionNode::Not(0)],
+            // child index out of range
+            vec![
+                &env,
+                ConditionNode::And(vec![&env, 1, 2]),
+                ConditionNode::Clause(0),
+            ],
+            // empty operator
+            vec![&env, ConditionNode::Or(Vec::new(&env))],
+            too_large,
+        ];
+        for tree in invalid {
+            let cfg = composite_config(&env, Address::generate(&env), conditions.clone(), tree);
+            assert_eq!(
+                client.try_register(&cfg),
+                Err(Ok(Error::InvalidCondition.into()))
+            );
+        }
+    }
+
+    #[test]
+    fn test_update_task_conditions() {
+        let (env, id) = setup();
+        let client = SoroTaskContractClient::new(&env, &id);
+        let mock = Address::generate(&env);
+
+        let task_id = register_funded(&env, &client, &base_config(&env, Address::generate(&env)));
+        let conditions = vec![&env, clause(&env, &mock, "yes")];
+
+        // A tree referencing a clause that is not supplied is rejected.
+        let dangling = TaskPatch {
+            condition_tree: Some(vec![&env, ConditionNode::Clause(0)]),
+            ..empty_patch(&env)
+        };
+        assert_eq!(
+            client.try_update_task(&task_id, &dangling),
+            Err(Ok(Error::InvalidCondition.into()))
+        );
+
+        let patch = TaskPatch {
+            conditions: Some(conditions.clone()),
+            condition_tree: Some(vec![&env, ConditionNode::Clause(0)]),
+            ..empty_patch(&env)
+        };
+        client.update_task(&task_id, &patch);
+        let stored = client.get_task(&task_id).unwrap();
+        assert_eq!(stored.conditions, conditions);
+        assert_eq!(stored.condition_tree.len(), 1);
+    }
+
+    // ── Cron schedules ───────────────────────────────────────────────────────
+
+    /// 2024-01-01 00:00:00 UTC, a Monday.
+    const JAN_1_2024: u64 = 1_704_067_200;
+    const DAY: u64 = 86_400;
+
+    #[test]
+    fn test_parse_cron_entrypoint() {
+        let (env, id) = setup();
+        let client = SoroTaskContractClient::new(&env, &id);
+
+        let schedule = client.parse_cron(&String::from_str(&env, "0 0 * * 1"));
+        assert_eq!(schedule.minutes, 1);
+        assert_eq!(schedule.hours, 1);
+        assert_eq!(schedule.days_of_week, 1 << 1);
+
+        assert_eq!(
+            client.try_parse_cron(&String::from_str(&env, "0 0 * *")),
+            Err(Ok(Error::InvalidSchedule.into()))
+        );
+    }
+
+    /// A weekly cron task runs on Monday 00:00 UTC and then waits a week.
+    #[test]
+    fn test_execute_cron_schedule() {
+        let (env, id) = setup();
+        let client = SoroTaskContractClient::new(&env, &id);
+        let keeper = Address::generate(&env);
+
+        let target = env.register_contract(None, MockTarget);
+        let weekly = client.parse_cron(&String::from_str(&env, "0 0 * * 1"));
+        let cfg = TaskParams {
+            schedule: Schedule::Cron(weekly),
+            start_at: JAN_1_2024 - DAY,
+            ..base_config(&env, target)
+        };
+        let task_id = register_funded(&env, &client, &cfg);
+
+        set_timestamp(&env, JAN_1_2024 - 60);
+        assert_eq!(
+            client.execute(&keeper, &task_id),
+            ExecutionOutcome::NotDue(JAN_1_2024)
+        );
+        assert!(client.monitor(&1, &10).is_empty());
+
+        // A keeper arriving late still runs; the next slot is the next Monday.
+        set_timestamp(&env, JAN_1_2024 + 600);
+        assert_eq!(
+            client.monitor(&1, &10),
+            vec![
+                &env,
+                DueTask {
+                    task_id,
+                    next_run_at: JAN_1_2024
+                }
+            ]
+        );
+        assert_eq!(
+            client.execute(&keeper, &task_id),
+            ExecutionOutcome::Executed
+        );
+
+        set_timestamp(&env, JAN_1_2024 + 3 * DAY);
+        assert_eq!(
+            client.execute(&keeper, &task_id),
+            ExecutionOutcome::NotDue(JAN_1_2024 + 7 * DAY)
+        );
+    }
+
+    #[test]
+    fn test_register_invalid_cron_schedule() {
+        let (env, id) = setup();
+        let client = SoroTaskContractClient::new(&env, &id);
+
+        let never = CronSchedule {
+            minutes: 1,
+            hours: 1,
+            days_of_month: 1 << 31,
+            months: 1 << 2 | 1 << 4,
+            days_of_week: 0x7f,
+        };
+        let out_of_range = CronSchedule {
+            minutes: 1 << 60,
+            ..never.clone()
+        };
+        for schedule in [never, out_of_range] {
+            let cfg = TaskParams {
+                schedule: Schedule::Cron(schedule),
+                ..base_config(&env, Address::generate(&env))
+            };
+            assert_eq!(
+                client.try_register(&cfg),
+                Err(Ok(Error::InvalidSchedule.into()))
+            );
+        }
+    }
+
+    #[test]
+    fn test_set_schedule() {
+        let (env, id) = setup();
+        let client = SoroTaskContractClient::new(&env, &id);
+
+        let cfg = base_config(&env, Address::generate(&env));
+        let task_id = register_funded(&env, &client, &cfg);
+        let monthly = client.parse_cron(&String::from_str(&env, "0 0 1 * *"));
+
+        client.set_schedule(&task_id, &Schedule::Cron(monthly.clone()));
+        assert_eq!(env.auths()[0].0, cfg.creator);
+        assert_eq!(last_event_name(&env), Symbol::new(&env, "TaskUpdated"));
+        assert_eq!(
+            client.get_task(&task_id).unwrap().schedule,
+            Schedule::Cron(monthly)
+        );
+
+        assert_eq!(
+            client.try_set_schedule(&task_id, &Schedule::Interval(0)),
+            Err(Ok(Error::InvalidInterval.into()))
+        );
+    }
+
+    // ── Anchored schedules ───────────────────────────────────────────────────
+
+    const HOUR: u64 = 3_600;
+
+    fn anchored_config(env: &Env, target: Address, missed: MissedRunPolicy) -> TaskParams {
+        TaskParams {
+            function: Symbol::new(env, "tick"),
+            schedule: Schedule::Anchored(AnchoredSchedule {
+                anchor: JAN_1_2024,
+                interval: HOUR,
+                missed,
+            }),
+            ..base_config(env, target)
+        }
+    }
+
+    fn ticks(env: &Env, target: &Address) -> u32 {
+        env.invoke_contract(target, &Symbol::new(env, "ticks"), Vec::new(env))
+    }
+
+    /// Late runs do not shift the schedule: slots stay on the anchor grid.
+    #[test]
+    fn test_anchored_schedule_does_not_drift() {
+        let (env, id) = setup();
+        let client = SoroTaskContractClient::new(&env, &id);
+        let keeper = Address::generate(&env);
+
+        let target = env.register_contract(None, MockTarget);
+        let cfg = anchored_config(&env, target, MissedRunPolicy::RunOnce);
+        let task_id = register_funded(&env, &client, &cfg);
+
+        set_timestamp(&env, JAN_1_2024 - 1);
+        assert_eq!(
+            client.execute(&keeper, &task_id),
+            ExecutionOutcome::NotDue(JAN_1_2024)
+        );
+
+        set_timestamp(&env, JAN_1_2024 + 900);
+        assert_eq!(
+            client.execute(&keeper, &task_id),
+            ExecutionOutcome::Executed
+        );
+        assert_eq!(client.get_task(&task_id).unwrap().last_run, JAN_1_2024);
+
+        set_timestamp(&env, JAN_1_2024 + HOUR + 1_800);
+        assert_eq!(
+            client.execute(&keeper, &task_id),
+            ExecutionOutcome::Executed
+        );
+        assert_eq!(
+            client.execute(&keeper, &task_id),
+            ExecutionOutcome::NotDue(JAN_1_2024 + 2 * HOUR)
+        );
+    }
+
+    #[test]
+    fn test_anchored_run_once_realigns_after_missed_slots() {
+        let (env, id) = setup();
+        let client = SoroTaskContractClient::new(&env, &id);
+        let keeper = Address::generate(&env);
+
+        let target = env.register_contract(None, MockTarget);
+        let cfg = anchored_config(&env, target.clone(), MissedRunPolicy::RunOnce);
+        let task_id = register_funded(&env, &client, &cfg);
+
+        set_timestamp(&env, JAN_1_2024 + 3 * HOUR + 60);
+        assert_eq!(
+            client.execute(&keeper, &task_id),
+            ExecutionOutcome::Executed
+        );
+        assert_eq!(ticks(&env, &target), 1);
+
+        let stored = client.get_task(&task_id).unwrap();
+        assert_eq!(stored.last_run, JAN_1_2024 + 3 * HOUR);
+        assert_eq!(stored.gas_balance, 990);
+        assert_eq!(balance(&env, &client.get_token(), &keeper), 10);
+    }
+
+    #[test]
+    fn test_anchored_skip_drops_missed_slots() {
+        let (env, id) = setup();
+        let client = SoroTaskContractClient::new(&env, &id);
+        let keeper = Address::generate(&env);
+
+        let target = env.register_contract(None, MockTarget);
+        let cfg = anchored_config(&env, target.clone(), MissedRunPolicy::Skip);
+        let task_id = register_funded(&env, &client, &cfg);
+
+        // Inside its own slot the task runs normally.
+        set_timestamp(&env, JAN_1_2024 + 60);
+        assert_eq!(
+            client.execute(&keeper, &task_id),
+            ExecutionOutcome::Executed
+        );
+
+        // Two slots were missed: nothing runs and no fee is paid.
+        set_timestamp(&env, JAN_1_2024 + 2 * HOUR + 60);
+        assert_eq!(
+            client.execute(&keeper, &task_id),
+            ExecutionOutcome::SlotsSkipped(JAN_1_2024 + 3 * HOUR)
+        );
+        assert_eq!(last_event_name(&env), Symbol::new(&env, "SlotsSkipped"));
+        assert_eq!(ticks(&env, &target), 1);
+
+        let stored = client.get_task(&task_id).unwrap();
+        assert_eq!(stored.last_run, JAN_1_2024 + 2 * HOUR);
+        assert_eq!(stored.gas_balance, 990);
+        assert_eq!(balance(&env, &client.get_token(), &keeper), 10);
+    }
+
+    #[test]
+    fn test_anchored_catch_up_runs_each_slot_up_to_cap() {
+        let (env, id) = setup();
+        let client = SoroTaskContractClient::new(&env, &id);
+        let keeper = Address::generate(&env);
+
+        let target = env.register_contract(None, MockTarget);
+        let cfg = anchored_config(&env, target.clone(), MissedRunPolicy::CatchUp(3));
+        let task_id = register_funded(&env, &client, &cfg);
+
+        // Five slots are pending (anchor .. anchor + 4h); three are served
+        // and the rest dropped.
+        set_timestamp(&env, JAN_1_2024 + 4 * HOUR + 60);
+        assert_eq!(
+            client.execute(&keeper, &task_id),
+            ExecutionOutcome::Executed
+        );
+        assert_eq!(ticks(&env, &target), 3);
+
+        let stored = client.get_task(&task_id).unwrap();
+        assert_eq!(stored.last_run, JAN_1_2024 + 4 * HOUR);
+        assert_eq!(stored.gas_balance, 970);
+        assert_eq!(balance(&env, &client.get_token(), &keeper), 30);
+    }
+
+    /// Catch-up runs are limited by what the gas balance can pay for.
+    #[test]
+    fn test_anchored_catch_up_limited_by_balance() {
+        let (env, id) = setup();
+        let client = SoroTaskContractClient::new(&env, &id);
+        let keeper = Address::generate(&env);
+
+        let target = env.register_contract(None, MockTarget);
+        let cfg = TaskParams {
+            gas_balance: 25,
+            ..anchored_config(&env, target.clone(), MissedRunPolicy::CatchUp(10))
+        };
+        let task_id = register_funded(&env, &client, &cfg);
+
+        set_timestamp(&env, JAN_1_2024 + 5 * HOUR);
+        let result = client.execute_batch(&keeper, &vec![&env, task_id]);
+        assert_eq!(result.outcomes, vec![&env, ExecutionOutcome::Executed]);
+        assert_eq!(result.total_reward, 20);
+        assert_eq!(ticks(&env, &target), 2);
+        assert_eq!(client.get_task(&task_id).unwrap().gas_balance, 5);
+    }
+
+    #[test]
+    fn test_register_invalid_anchored_schedule() {
+        let (env, id) = setup();
+        let client = SoroTaskContractClient::new(&env, &id);
+        let target = Address::generate(&env);
+
+        let cfg = anchored_config(&env, target.clone(), MissedRunPolicy::CatchUp(0));
+        assert_eq!(
+            client.try_register(&cfg),
+            Err(Ok(Error::InvalidSchedule.into()))
+        );
+
+        let cfg = TaskParams {
+            schedule: Schedule::Anchored(AnchoredSchedule {
+                anchor: JAN_1_2024,
+                interval: 0,
+                missed: MissedRunPolicy::Skip,
+            }),
+            ..base_config(&env, target)
+        };
+        assert_eq!(
+            client.try_register(&cfg),
+            Err(Ok(Error::InvalidInterval.into()))
+        );
+    }
+
+    // ── Start time and expiry ────────────────────────────────────────────────
+
+    #[test]
+    fn test_task_not_due_before_start_at() {
+        let (env, id) = setup();
+        let client = SoroTaskContractClient::new(&env, &id);
+        let keeper = Address::generate(&env);
+
+        let target = env.register_contract(None, MockTarget);
+        let cfg = TaskParams {
+            start_at: 10_000,
+            ..base_config(&env, target)
+        };
+        let task_id = register_funded(&env, &client, &cfg);
+
+        // last_run 0 + interval 3_600 would already be due.
+        set_timestamp(&env, 5_000);
+        assert!(client.monitor(&1, &10).is_empty());
+        assert_eq!(
+            client.execute(&keeper, &task_id),
+            ExecutionOutcome::NotDue(10_000)
+        );
+
+        set_timestamp(&env, 10_000);
+        assert_eq!(client.monitor(&1, &10).len(), 1);
+        assert_eq!(
+            client.execute(&keeper, &task_id),
+            ExecutionOutcome::Executed
+        );
+    }
+
+    /// A cron task's first slot is the first one at or after `start_at`.
+    #[test]
+    fn test_cron_schedule_honors_start_at() {
+        let (env, id) = setup();
+        let client = SoroTaskContractClient::new(&env, &id);
+        let keeper = Address::generate(&env);
+
+        let daily = client.parse_cron(&String::from_str(&env, "0 0 * * *"));
+        let cfg = TaskParams {
+            schedule: Schedule::Cron(daily),
+            start_at: JAN_1_2024 + DAY,
+            ..base_config(&env, env.register_contract(None, MockTarget))
+        };
+        let task_id = register_funded(&env, &client, &cfg);
+
+        set_timestamp(&env, JAN_1_2024 + 60);
+        assert_eq!(
+            client.execute(&keeper, &task_id),
+            ExecutionOutcome::NotDue(JAN_1_2024 + DAY)
+        );
+    }
+
+    #[test]
+    fn test_expired_task_finishes_and_refunds() {
+        let (env, id) = setup();
+        let client = SoroTaskContractClient::new(&env, &id);
+        let keeper = Address::generate(&env);
+        let token = client.get_token();
+
+        let target = env.register_contract(None, MockTarget);
+        let cfg = TaskParams {
+            end_at: Some(10_000),
+            ..base_config(&env, target)
+        };
+        let task_id = register_funded(&env, &client, &cfg);
+
+        set_timestamp(&env, 4_000);
+        client.execute(&keeper, &task_id);
+
+        set_timestamp(&env, 10_000);
+        assert!(client.monitor(&1, &10).is_empty());
+        assert_eq!(
+            client.execute(&keeper, &task_id),
+            ExecutionOutcome::Finished
+        );
+        assert_eq!(last_event_name(&env), Symbol::new(&env, "TaskFinished"));
+
+        let stored = client.get_task(&task_id).unwrap();
+        assert_eq!(stored.status, TaskStatus::Finished);
+        assert_eq!(stored.gas_balance, 0);
+        assert_eq!(balance(&env, &token, &cfg.creator), 990);
+        assert_eq!(balance(&env, &token, &keeper), 10);
+
+        assert_eq!(
+            client.try_execute(&keeper, &task_id),
+            Err(Ok(Error::TaskFinished.into()))
+        );
+        assert_eq!(
+            client.try_deposit_gas(&task_id, &100),
+            Err(Ok(Error::TaskFinished.into()))
+        );
+        assert_eq!(
+            client.try_resume_task(&task_id),
+            Err(Ok(Error::TaskFinished.into()))
+        );
+    }
+
+    /// Expiry is enforced even while a task is paused.
+    #[test]
+    fn test_paused_task_finishes_after_end_at() {
+        let (env, id) = setup();
+        let client = SoroTaskContractClient::new(&env, &id);
+        let keeper = Address::generate(&env);
+
+        let cfg = TaskParams {
+            end_at: Some(10_000),
+            ..base_config(&env, env.register_contract(None, MockTarget))
+        };
+        let task_id = register_funded(&env, &client, &cfg);
+        client.pause_task(&task_id);
+
+        set_timestamp(&env, 20_000);
+        let result = client.execute_batch(&keeper, &vec![&env, task_id]);
+        assert_eq!(result.outcomes, vec![&env, ExecutionOutcome::Finished]);
+        assert_eq!(result.total_reward, 0);
+        assert_eq!(balance(&env, &client.get_token(), &cfg.creator), 1_000);
+    }
+
+    #[test]
+    fn test_register_invalid_time_window() {
+        let (env, id) = setup();
+        let client = SoroTaskContractClient::new(&env, &id);
+        set_timestamp(&env, 5_000);
+
+        for (start_at, end_at) in [(8_000, 8_000), (0, 5_000)] {
+            let cfg = TaskParams {
+                start_at,
+                end_at: Some(end_at),
+                ..base_config(&env, Address::generate(&env))
+            };
+            assert_eq!(
+                client.try_register(&cfg),
+                Err(Ok(Error::InvalidTimeWindow.into()))
+            );
+        }
+    }
+
+    // ── Execution limits ─────────────────────────────────────────────────────
+
+    /// A task with `max_executions` completes after that many runs and
+    /// refunds what is left of its balance.
+    #[test]
+    fn test_max_executions_completes_task() {
+        let (env, id) = setup();
+        let client = SoroTaskContractClient::new(&env, &id);
+        let keeper = Address::generate(&env);
+        let token = client.get_token();
+
+        let cfg = TaskParams {
+            max_executions: Some(3),
+            ..base_config(&env, env.register_contract(None, MockTarget))
+        };
+        let task_id = register_funded(&env, &client, &cfg);
+
+        for run in 1..=3u64 {
+            set_timestamp(&env, run * 3_600);
+            assert_eq!(
+                client.execute(&keeper, &task_id),
+                ExecutionOutcome::Executed
+            );
+        }
+        assert_eq!(last_event_name(&env), Symbol::new(&env, "KeeperPaid"));
+
+        let stored = client.get_task(&task_id).unwrap();
+        assert_eq!(stored.execution_count, 3);
+        assert_eq!(stored.status, TaskStatus::Finished);
+        assert_eq!(stored.gas_balance, 0);
+        assert_eq!(balance(&env, &token, &keeper), 30);
+        assert_eq!(balance(&env, &token, &cfg.creator), 970);
+
+        set_timestamp(&env, 4 * 3_600);
+        assert!(client.monitor(&1, &10).is_empty());
+        assert_eq!(
+            client.try_execute(&keeper, &task_id),
+            Err(Ok(Error::TaskFinished.into()))
+        );
+    }
+
+    /// Declined and failed attempts do not count towards the limit.
+    #[test]
+    fn test_one_shot_counts_only_successful_runs() {
+        let (env, id) = setup();
+        let client = SoroTaskContractClient::new(&env, &id);
+        let keeper = Address::generate(&env);
+
+        let cfg = TaskParams {
+            function: Symbol::new(&env, "fail"),
+            max_executions: Some(1),
+            ..base_config(&env, env.register_contract(None, MockTarget))
+        };
+        let task_id = register_funded(&env, &client, &cfg);
+
+        set_timestamp(&env, 3_600);
+        let result = client.execute_batch(&keeper, &vec![&env, task_id]);
+        assert_eq!(result.outcomes, vec![&env, ExecutionOutcome::TargetFailed]);
+        assert_eq!(client.get_task(&task_id).unwrap().execution_count, 0);
+
+        let patch = TaskPatch {
+            function: Some(Symbol::new(&env, "ping")),
+            ..empty_patch(&env)
+        };
+        client.update_task(&task_id, &patch);
+        assert_eq!(
+            client.execute(&keeper, &task_id),
+            ExecutionOutcome::Executed
+        );
+
+        let stored = client.get_task(&task_id).unwrap();
+        assert_eq!(stored.execution_count, 1);
+        assert_eq!(stored.status, TaskStatus::Finished);
+        assert!(env
+            .events()
+            .all()
+            .iter()
+            .any(|e| Symbol::from_val(&env, &e.1.get(0).unwrap())
+                == Symbol::new(&env, "TaskCompleted")));
+    }
+
+    /// Catch-up runs stop at the execution limit.
+    #[test]
+    fn test_max_executions_caps_catch_up_runs() {
+        let (env, id) = setup();
+        let client = SoroTaskContractClient::new(&env, &id);
+        let keeper = Address::generate(&env);
+
+        let target = env.register_contract(None, MockTarget);
+        let cfg = TaskParams {
+            max_executions: Some(2),
+            ..anchored_config(&env, target.clone(), MissedRunPolicy::CatchUp(5))
+        };
+        let task_id = register_funded(&env, &client, &cfg);
+
+        set_timestamp(&env, JAN_1_2024 + 4 * HOUR);
+        client.execute(&keeper, &task_id);
+        assert_eq!(ticks(&env, &target), 2);
+        assert_eq!(
+            client.get_task(&task_id).unwrap().status,
+            TaskStatus::Finished
+        );
+    }
+
+    #[test]
+    fn test_register_zero_max_executions() {
+        let (env, id) = setup();
+        let client = SoroTaskContractClient::new(&env, &id);
+
+        let cfg = TaskParams {
+            max_executions: Some(0),
+            ..base_config(&env, Address::generate(&env))
+        };
+        assert_eq!(
+            client.try_register(&cfg),
+            Err(Ok(Error::InvalidExecutionLimit.into()))
+        );
+    }
+
+    // ── Storage migration ────────────────────────────────────────────────────
+
+    /// Writes a task in the first-release layout, as an old deployment would
+    /// have left it.
+    fn store_legacy_task(env: &Env, contract_id: &Address, task_id: u64, task: &LegacyTaskConfig) {
+        env.as_contract(contract_id, || {
+            env.storage()
+                .persistent()
+                .set(&DataKey::Task(task_id), task);
+            env.storage().persistent().set(&DataKey::Counter, &task_id);
+        });
+    }
+
+    #[test]
+    fn test_migrate_legacy_tasks() {
+        let (env, id) = setup();
+        let client = SoroTaskContractClient::new(&env, &id);
+        let keeper = Address::generate(&env);
+
+        let target = env.register_contract(None, MockTarget);
+        let current = register_funded(&env, &client, &base_config(&env, target.clone()));
+        let legacy = LegacyTaskConfig {
+            creator: Address::generate(&env),
+            target,
+            function: Symbol::new(&env, "ping"),
+            args: Vec::new(&env),
+            resolver: None,
+            interval: 600,
+            last_run: 7_000,
+            gas_balance: 500,
+        };
+        store_legacy_task(&env, &id, 2, &legacy);
+
+        assert_eq!(client.migrate_tasks(&1, &10), 1);
+        assert_eq!(last_event_name(&env), Symbol::new(&env, "TaskMigrated"));
+
+        let migrated = client.get_task(&2).unwrap();
+        assert_eq!(migrated.creator, legacy.creator);
+        assert_eq!(migrated.schedule, Schedule::Interval(600));
+        assert_eq!(migrated.last_run, 7_000);
+        assert_eq!(migrated.gas_balance, 0);
+        assert_eq!(migrated.status, TaskStatus::Active);
+        assert_eq!(client.get_task(&current).unwrap().gas_balance, 1_000);
+
+        // Already migrated; a second pass is a no-op.
+        assert_eq!(client.migrate_tasks(&1, &10), 0);
+
+        set_timestamp(&env, 7_600);
+        assert_eq!(client.execute(&keeper, &2), ExecutionOutcome::Executed);
+    }
+
+    /// A legacy task calling the fee token is migrated as finished, so it
+    /// cannot move other tasks' escrow.
+    #[test]
+    fn test_migrate_finishes_tasks_calling_protected_addresses() {
+        let (env, id) = setup();
+        let client = SoroTaskContractClient::new(&env, &id);
+        let keeper = Address::generate(&env);
+        let token = client.get_token();
+
+        let victim = register_funded(
+            &env,
+            &client,
+            &base_config(&env, env.register_contract(None, MockTarget)),
+        );
+        let attacker = Address::generate(&env);
+        let legacy = LegacyTaskConfig {
+            creator: attacker.clone(),
+            target: token.clone(),
+            function: Symbol::new(&env, "transfer"),
+            args: vec![
+                &env,
+                id.into_val(&env),
+                attacker.into_val(&env),
+                1_000_i128.into_val(&env),
+            ],
+            resolver: None,
+            interval: 600,
+            last_run: 0,
+            gas_balance: 0,
+        };
+        store_legacy_task(&env, &id, 2, &legacy);
+
+        assert_eq!(client.migrate_tasks(&1, &10), 1);
+        assert_eq!(client.get_task(&2).unwrap().status, TaskStatus::Finished);
+
+        set_timestamp(&env, 3_600);
+        assert_eq!(
+            client.try_execute(&keeper, &2),
+            Err(Ok(Error::TaskFinished.into()))
+        );
+        assert_eq!(balance(&env, &token, &attacker), 0);
+        assert_eq!(client.get_task(&victim).unwrap().gas_balance, 1_000);
+    }
+
+    #[test]
+    fn test_migrate_tasks_requires_admin() {
+        let env = Env::default();
+        let id = env.register_contract(None, SoroTaskContract);
+        let client = SoroTaskContractClient::new(&env, &id);
+        let admin = Address::generate(&env);
+        env.mock_all_auths();
+        client.initialize(&admin, &Address::generate(&env));
+
+        client.migrate_tasks(&1, &10);
+        assert_eq!(env.auths()[0].0, admin);
+    }
+
+    // ── Keeper registry ──────────────────────────────────────────────────────
+
+    /// Mints `stake` to a fresh keeper and bonds it.
+    fn bonded_keeper(env: &Env, client: &SoroTaskContractClient, stake: i128) -> Address {
+        let keeper = Address::generate(env);
+        mint(env, &client.get_token(), &keeper, stake);
+        client.register_keeper(&keeper, &stake);
+        keeper
+    }
+
+    #[test]
+    fn test_register_keeper_locks_stake() {
+        let (env, id) = setup();
+        let client = SoroTaskContractClient::new(&env, &id);
+        let token = client.get_token();
+
+        let keeper = bonded_keeper(&env, &client, 300);
+        assert_eq!(balance(&env, &token, &keeper), 0);
+        assert_eq!(balance(&env, &token, &id), 300);
+        assert_eq!(last_event_name(&env), Symbol::new(&env, "KeeperRegistered"));
+
+        // Topping up keeps a single registry entry.
+        mint(&env, &token, &keeper, 200);
+        client.register_keeper(&keeper, &200);
+        assert_eq!(
+            client.get_keeper(&keeper),
+            Some(KeeperInfo {
+                stake: 500,
+                unbonds_at: None
+            })
+        );
+        assert_eq!(client.active_keepers(), vec![&env, keeper]);
+    }
+
+    #[test]
+    fn test_register_keeper_below_min_stake() {
+        let (env, id) = setup();
+        let client = SoroTaskContractClient::new(&env, &id);
+
+        client.set_keeper_config(&KeeperConfig {
+            min_stake: 1_000,
+            unbonding_period: DAY,
+            ..client.get_keeper_config()
+        });
+        let keeper = Address::generate(&env);
+        mint(&env, &client.get_token(), &keeper, 999);
+        assert_eq!(
+            client.try_register_keeper(&keeper, &999),
+            Err(Ok(Error::InsufficientStake.into()))
+        );
+        assert_eq!(
+            client.try_register_keeper(&keeper, &0),
+            Err(Ok(Error::InvalidAmount.into()))
+        );
+    }
+
+    #[test]
+    fn test_unregister_keeper_unbonding_delay() {
+        let (env, id) = setup();
+        let client = SoroTaskContractClient::new(&env, &id);
+        let token = client.get_token();
+
+        let keeper = bonded_keeper(&env, &client, 300);
+        let other = bonded_keeper(&env, &client, 100);
+        assert_eq!(
+            client.try_withdraw_stake(&keeper),
+            Err(Ok(Error::StakeLocked.into()))
+        );
+
+        set_timestamp(&env, 1_000);
+        client.unregister_keeper(&keeper);
+        assert_eq!(env.auths()[0].0, keeper);
+        assert_eq!(
+            client.get_keeper(&keeper).unwrap().unbonds_at,
+            Some(1_000 + 7 * DAY)
+        );
+        assert_eq!(client.active_keepers(), vec![&env, other]);
+        assert_eq!(
+            client.try_unregister_keeper(&keeper),
+            Err(Ok(Error::KeeperUnbonding.into()))
+        );
+        assert_eq!(
+            client.try_register_keeper(&keeper, &10),
+            Err(Ok(Error::KeeperUnbonding.into()))
+        );
+
+        set_timestamp(&env, 1_000 + 7 * DAY - 1);
+        assert_eq!(
+            client.try_withdraw_stake(&keeper),
+            Err(Ok(Error::StakeLocked.into()))
+        );
+
+        set_timestamp(&env, 1_000 + 7 * DAY);
+        client.withdraw_stake(&keeper);
+        assert_eq!(balance(&env, &token, &keeper), 300);
+        assert_eq!(client.get_keeper(&keeper), None);
+        assert_eq!(
+            client.try_unregister_keeper(&keeper),
+            Err(Ok(Error::KeeperNotRegistered.into()))
+        );
+    }
+
+    #[test]
+    fn test_bonded_only_task_rejects_unbonded_keepers() {
+        let (env, id) = setup();
+        let client = SoroTaskContractClient::new(&env, &id);
+
+        let cfg = TaskParams {
+            bonded_only: true,
+            ..base_config(&env, env.register_contract(None, MockTarget))
+        };
+        let task_id = register_funded(&env, &client, &cfg);
+        set_timestamp(&env, 3_600);
+
+        let stranger = Address::generate(&env);
+        assert_eq!(
+            client.try_execute(&stranger, &task_id),
+            Err(Ok(Error::KeeperNotBonded.into()))
+        );
+
+        let keeper = bonded_keeper(&env, &client, 100);
+        client.unregister_keeper(&keeper);
+        assert_eq!(
+            client.execute_batch(&keeper, &vec![&env, task_id]).outcomes,
+            vec![
+                &env,
+                ExecutionOutcome::Rejected(Error::KeeperNotBonded as u32)
+            ]
+        );
+
+        let keeper = bonded_keeper(&env, &client, 100);
+        assert_eq!(
+            client.execute(&keeper, &task_id),
+            ExecutionOutcome::Executed
+        );
+    }
+
+    /// Raising the minimum stake unbonds keepers below it for gated tasks.
+    #[test]
+    fn test_bonded_only_honors_current_min_stake() {
+        let (env, id) = setup();
+        let client = SoroTaskContractClient::new(&env, &id);
+
+        let cfg = TaskParams {
+            bonded_only: true,
+            ..base_config(&env, env.register_contract(None, MockTarget))
+        };
+        let task_id = register_funded(&env, &client, &cfg);
+        let keeper = bonded_keeper(&env, &client, 100);
+
+        client.set_keeper_config(&KeeperConfig {
+            min_stake: 500,
+            unbonding_period: DAY,
+            ..client.get_keeper_config()
+        });
+        set_timestamp(&env, 3_600);
+        assert_eq!(
+            client.try_execute(&keeper, &task_id),
+            Err(Ok(Error::KeeperNotBonded.into()))
+        );
+    }
+
+    // ── Slashing ─────────────────────────────────────────────────────────────
+
+    /// A due task claimed by a bonded keeper with 1_000 staked.
+    fn claimed_task(
+        env: &Env,
+        client: &SoroTaskContractClient,
+        cfg: &TaskParams,
+    ) -> (u64, Address) {
+        let task_id = register_funded(env, client, cfg);
+        let keeper = bonded_keeper(env, client, 1_000);
+        client.claim_task(&keeper, &task_id);
+        (task_id, keeper)
+    }
+
+    #[test]
+    fn test_report_missed_slashes_assigned_keeper() {
+        let (env, id) = setup();
+        let client = SoroTaskContractClient::new(&env, &id);
+        let reporter = Address::generate(&env);
+
+        let cfg = base_config(&env, env.register_contract(None, MockTarget));
+        let (task_id, keeper) = claimed_task(&env, &client, &cfg);
+
+        // Due at 3_600; the default grace period is another hour.
+        set_timestamp(&env, 7_199);
+        assert_eq!(
+            client.try_report_missed(&reporter, &task_id),
+            Err(Ok(Error::NotMissed.into()))
+        );
+
+        set_timestamp(&env, 7_200);
+        assert_eq!(client.report_missed(&reporter, &task_id), 100);
+        assert_eq!(last_event_name(&env), Symbol::new(&env, "KeeperSlashed"));
+        assert_eq!(client.get_keeper(&keeper).unwrap().stake, 900);
+        assert_eq!(balance(&env, &client.get_token(), &reporter), 50);
+
+        let stored = client.get_task(&task_id).unwrap();
+        assert_eq!(stored.gas_balance, 1_050);
+        assert_eq!(stored.assigned_keeper, None);
+
+        // The miss has been punished; the task is no longer assigned.
+        assert_eq!(
+            client.try_report_missed(&reporter, &task_id),
+            Err(Ok(Error::TaskNotAssigned.into()))
+        );
+    }
+
+    /// The grace window starts at the claim, not at the original due time.
+    #[test]
+    fn test_report_missed_counts_from_claim() {
+        let (env, id) = setup();
+        let client = SoroTaskContractClient::new(&env, &id);
+        let reporter = Address::generate(&env);
+
+        let cfg = base_config(&env, env.register_contract(None, MockTarget));
+        set_timestamp(&env, 50_000);
+        let (task_id, _) = claimed_task(&env, &client, &cfg);
+
+        set_timestamp(&env, 50_000 + 3_599);
+        assert_eq!(
+            client.try_report_missed(&reporter, &task_id),
+            Err(Ok(Error::NotMissed.into()))
+        );
+        set_timestamp(&env, 50_000 + 3_600);
+        assert_eq!(client.report_missed(&reporter, &task_id), 100);
+    }
+
+    /// A task the keeper could not have run is not a miss.
+    #[test]
+    fn test_report_missed_requires_runnable_task() {
+        let (env, id) = setup();
+        let client = SoroTaskContractClient::new(&env, &id);
+        let reporter = Address::generate(&env);
+
+        let resolver = env.register_contract(None, resolver_false::MockResolverFalse);
+        let declined = TaskParams {
+            resolver: Some(resolver),
+            ..base_config(&env, env.register_contract(None, MockTarget))
+        };
+        let (declined_id, _) = claimed_task(&env, &client, &declined);
+        let (paused_id, _) =
+            claimed_task(&env, &client, &base_config(&env, Address::generate(&env)));
+        client.pause_task(&paused_id);
+
+        set_timestamp(&env, 50_000);
+        for task_id in [declined_id, paused_id] {
+            assert_eq!(
+                client.try_report_missed(&reporter, &task_id),
+                Err(Ok(Error::NotMissed.into()))
+            );
+        }
+    }
+
+    /// A task funded for its base fee but not for its per-argument run fee
+    /// is not a miss.
+    #[test]
+    fn test_report_missed_prices_run_fee() {
+        let (env, id) = setup();
+        let client = SoroTaskContractClient::new(&env, &id);
+
+        let cfg = TaskParams {
+            function: Symbol::new(&env, "add"),
+            args: vec![&env, 2i64.into_val(&env), 3i64.into_val(&env)],
+            gas_balance: 15,
+            fee_policy: FeePolicy::PerArg(5),
+            ..base_config(&env, env.register_contract(None, MockTarget))
+        };
+        let (task_id, keeper) = claimed_task(&env, &client, &cfg);
+
+        set_timestamp(&env, 50_000);
+        assert_eq!(
+            client.try_execute(&keeper, &task_id),
+            Err(Ok(Error::InsufficientBalance.into()))
+        );
+        assert_eq!(
+            client.try_report_missed(&Address::generate(&env), &task_id),
+            Err(Ok(Error::NotMissed.into()))
+        );
+    }
+
+    #[test]
+    fn test_claim_task() {
+        let (env, id) = setup();
+        let client = SoroTaskContractClient::new(&env, &id);
+
+        let cfg = base_config(&env, Address::generate(&env));
+        let (task_id, keeper) = claimed_task(&env, &client, &cfg);
+        assert_eq!(
+            client.get_task(&task_id).unwrap().assigned_keeper,
+            Some(keeper.clone())
+        );
+
+        let rival = bonded_keeper(&env, &client, 100);
+        assert_eq!(
+            client.try_claim_task(&rival, &task_id),
+            Err(Ok(Error::TaskAlreadyAssigned.into()))
+        );
+        assert_eq!(
+            client.try_claim_task(&Address::generate(&env), &task_id),
+            Err(Ok(Error::KeeperNotBonded.into()))
+        );
+
+        // An unbonding keeper's claim can be taken over.
+        client.unregister_keeper(&keeper);
+        client.claim_task(&rival, &task_id);
+        assert_eq!(
+            client.try_release_task(&keeper, &task_id),
+            Err(Ok(Error::TaskNotAssigned.into()))
+        );
+        client.release_task(&rival, &task_id);
+        assert_eq!(client.get_task(&task_id).unwrap().assigned_keeper, None);
+    }
+
+    // ── Exclusive windows ────────────────────────────────────────────────────
+
+    #[test]
+    fn test_designated_keeper_has_exclusive_window() {
+        let (env, id) = setup();
+        let client = SoroTaskContractClient::new(&env, &id);
+
+        let cfg = base_config(&env, env.register_contract(None, MockTarget));
+        let task_id = register_funded(&env, &client, &cfg);
+        assert_eq!(client.designated_keeper(&task_id), None);
+
+        let keepers = [
+            bonded_keeper(&env, &client, 100),
+            bonded_keeper(&env, &client, 100),
+        ];
+        let designated = client.designated_keeper(&task_id).unwrap();
+        let other = keepers.iter().find(|k| **k != designated).unwrap();
+
+        set_timestamp(&env, 3_600);
+        assert_eq!(
+            client.execute(other, &task_id),
+            ExecutionOutcome::Reserved(3_660)
+        );
+        assert_eq!(last_event_name(&env), Symbol::new(&env, "TaskReserved"));
+        assert_eq!(
+            client.execute(&designated, &task_id),
+            ExecutionOutcome::Executed
+        );
+    }
+
+    /// Assignment is a pure function of task ID and due time.
+    #[test]
+    fn test_designated_keeper_rotates_deterministically() {
+        let (env, id) = setup();
+        let client = SoroTaskContractClient::new(&env, &id);
+        let keeper = Address::generate(&env);
+
+        let cfg = base_config(&env, env.register_contract(None, MockTarget));
+        let task_id = register_funded(&env, &client, &cfg);
+        for _ in 0..3 {
+            bonded_keeper(&env, &client, 100);
+        }
+        let keepers = client.active_keepers();
+
+        let mut seen = Vec::<Address>::new(&env);
+        for _ in 0..8 {
+            let due_at = client.get_task(&task_id).unwrap().last_run + 3_600;
+            let mut seed = [0u8; 16];
+            seed[..8].copy_from_slice(&task_id.to_be_bytes());
+            seed[8..].copy_from_slice(&due_at.to_be_bytes());
+            let digest = env
+                .crypto()
+                .sha256(&Bytes::from_array(&env, &seed))
+                .to_array();
+            let index = u64::from_be_bytes(digest[..8].try_into().unwrap()) % 3;
+
+            let designated = client.designated_keeper(&task_id).unwrap();
+            assert_eq!(designated, keepers.get(index as u32).unwrap());
+            if !seen.contains(&designated) {
+                seen.push_back(designated);
+            }
+
+            set_timestamp(&env, due_at + 60);
+            client.execute(&keeper, &task_id);
+        }
+        assert!(seen.len() > 1, "assignment should rotate across runs");
+    }
+
+    #[test]
+    fn test_exclusive_window_opens_to_all_keepers() {
+        let (env, id) = setup();
+        let client = SoroTaskContractClient::new(&env, &id);
+
+        let cfg = base_config(&env, env.register_contract(None, MockTarget));
+        let task_id = register_funded(&env, &client, &cfg);
+        bonded_keeper(&env, &client, 100);
+
+        let outsider = Address::generate(&env);
+        set_timestamp(&env, 3_659);
+        assert_eq!(
+            client.execute(&outsider, &task_id),
+            ExecutionOutcome::Reserved(3_660)
+        );
+        set_timestamp(&env, 3_660);
+        assert_eq!(
+            client.execute(&outsider, &task_id),
+            ExecutionOutcome::Executed
+        );
+    }
+
+    /// A claimed task is reserved for its claimer rather than the rotation.
+    #[test]
+    fn test_claimed_task_reserved_for_claimer() {
+        let (env, id) = setup();
+        let client = SoroTaskContractClient::new(&env, &id);
+
+        let cfg = base_config(&env, env.register_contract(None, MockTarget));
+        let (task_id, claimer) = claimed_task(&env, &client, &cfg);
+        let other = bonded_keeper(&env, &client, 100);
+        assert_eq!(client.designated_keeper(&task_id), Some(claimer.clone()));
+
+        set_timestamp(&env, 3_600);
+        let result = client.execute_batch(&other, &vec![&env, task_id]);
+        assert_eq!(
+            result.outcomes,
+            vec![&env, ExecutionOutcome::Reserved(3_660)]
+        );
+        assert_eq!(
+            client.execute(&claimer, &task_id),
+            ExecutionOutcome::Executed
+        );
+    }
+
+    // ── Keeper allowlist ─────────────────────────────────────────────────────
+
+    #[test]
+    fn test_allowlist_restricts_execute() {
+        let (env, id) = setup();
+        let client = SoroTaskContractClient::new(&env, &id);
+        let bot = Address::generate(&env);
+        let stranger = Address::generate(&env);
+
+        let cfg = TaskParams {
+            allowed_keepers: vec![&env, bot.clone()],
+            ..base_config(&env, env.register_contract(None, MockTarget))
+        };
+        let task_id = register_funded(&env, &client, &cfg);
+
+        set_timestamp(&env, 3_600);
+        assert_eq!(
+            client.try_execute(&stranger, &task_id),
+            Err(Ok(Error::KeeperNotAllowed.into()))
+        );
+        assert_eq!(
+            client
+                .execute_batch(&stranger, &vec![&env, task_id])
+                .outcomes,
+            vec![
+                &env,
+                ExecutionOutcome::Rejected(Error::KeeperNotAllowed as u32)
+            ]
+        );
+        assert_eq!(client.execute(&bot, &task_id), ExecutionOutcome::Executed);
+        assert_eq!(env.auths()[0].0, bot);
+    }
+
+    #[test]
+    fn test_add_and_remove_allowed_keepers() {
+        let (env, id) = setup();
+        let client = SoroTaskContractClient::new(&env, &id);
+        let bot = Address::generate(&env);
+        let stranger = Address::generate(&env);
+
+        let cfg = base_config(&env, env.register_contract(None, MockTarget));
+        let task_id = register_funded(&env, &client, &cfg);
+
+        client.add_allowed_keeper(&task_id, &bot);
+        assert_eq!(env.auths()[0].0, cfg.creator);
+        assert_eq!(last_event_name(&env), Symbol::new(&env, "KeeperAllowed"));
+        client.add_allowed_keeper(&task_id, &bot);
+        assert_eq!(
+            client.get_task(&task_id).unwrap().allowed_keepers,
+            vec![&env, bot.clone()]
+        );
+
+        set_timestamp(&env, 3_600);
+        assert_eq!(
+            client.try_execute(&stranger, &task_id),
+            Err(Ok(Error::KeeperNotAllowed.into()))
+        );
+
+        // Emptying the list opens the task again.
+        client.remove_allowed_keeper(&task_id, &bot);
+        assert_eq!(env.auths()[0].0, cfg.creator);
+        assert_eq!(last_event_name(&env), Symbol::new(&env, "KeeperDisallowed"));
+        assert_eq!(
+            client.execute(&stranger, &task_id),
+            ExecutionOutcome::Executed
+        );
+    }
+
+    /// Allowlisted tasks are not reserved for keepers outside the list.
+    #[test]
+    fn test_allowlist_skips_keeper_rotation() {
+        let (env, id) = setup();
+        let client = SoroTaskContractClient::new(&env, &id);
+        let bot = Address::generate(&env);
+
+        let cfg = TaskParams {
+            allowed_keepers: vec![&env, bot.clone()],
+            ..base_config(&env, env.register_contract(None, MockTarget))
+        };
+        let task_id = register_funded(&env, &client, &cfg);
+        let bonded = bonded_keeper(&env, &client, 100);
+
+        assert_eq!(client.designated_keeper(&task_id), None);
+        assert_eq!(
+            client.try_claim_task(&bonded, &task_id),
+            Err(Ok(Error::KeeperNotAllowed.into()))
+        );
+        set_timestamp(&env, 3_600);
+        assert_eq!(client.execute(&bot, &task_id), ExecutionOutcome::Executed);
+    }
+
+    // ── Keeper statistics ────────────────────────────────────────────────────
+
+    #[test]
+    fn test_keeper_stats_track_executions() {
+        let (env, id) = setup();
         let client = SoroTaskContractClient::new(&env, &id);
+        let keeper = Address::generate(&env);
 
         let target = env.register_contract(None, MockTarget);
-        let cfg = base_config(&env, target.clone());
-        let task_id = client.register(&cfg);
+        let ok = register_funded(&env, &client, &base_config(&env, target.clone()));
+        let broken = TaskParams {
+            function: Symbol::new(&env, "fail"),
+            ..base_config(&env, target)
+        };
+        let broken = register_funded(&env, &client, &broken);
+        assert_eq!(client.get_keeper_stats(&keeper), KeeperStats::default());
+
+        // On time: exactly at the due time.
+        set_timestamp(&env, 3_600);
+        client.execute_batch(&keeper, &vec![&env, ok, broken]);
+        // Late: 600 seconds after the next due time.
+        set_timestamp(&env, 3_600 + 3_600 + 600);
+        client.execute(&keeper, &ok);
+
+        assert_eq!(
+            client.get_keeper_stats(&keeper),
+            KeeperStats {
+                executions: 2,
+                rewards: 20,
+                failures: 1,
+                late_executions: 1,
+                total_delay: 600,
+            }
+        );
+    }
+
+    /// Declined or not-due attempts leave the stats untouched.
+    #[test]
+    fn test_keeper_stats_ignore_noop_outcomes() {
+        let (env, id) = setup();
+        let client = SoroTaskContractClient::new(&env, &id);
+        let keeper = Address::generate(&env);
+
+        let resolver = env.register_contract(None, resolver_false::MockResolverFalse);
+        let cfg = TaskParams {
+            resolver: Some(resolver),
+            ..base_config(&env, env.register_contract(None, MockTarget))
+        };
+        let task_id = register_funded(&env, &client, &cfg);
+
+        client.execute(&keeper, &task_id);
+        set_timestamp(&env, 3_600);
+        client.execute(&keeper, &task_id);
+        assert_eq!(client.get_keeper_stats(&keeper), KeeperStats::default());
+        assert!(client.top_keepers(&10).is_empty());
+    }
+
+    #[test]
+    fn test_top_keepers_leaderboard() {
+        let (env, id) = setup();
+        let client = SoroTaskContractClient::new(&env, &id);
+        let keepers = [
+            Address::generate(&env),
+            Address::generate(&env),
+            Address::generate(&env),
+        ];
+
+        register_many(&env, &client, &[3_600; 5]);
+        let broken = TaskParams {
+            function: Symbol::new(&env, "fail"),
+            ..base_config(&env, env.register_contract(None, MockTarget))
+        };
+        let broken = register_funded(&env, &client, &broken);
+
+        // keepers[1] runs three tasks, keepers[0] and keepers[2] one each,
+        // with keepers[2] also failing one.
+        set_timestamp(&env, 3_600);
+        let runs = [(1, 1..4), (0, 4..5), (2, 5..6)];
+        for (keeper, task_ids) in runs {
+            for task_id in task_ids {
+                client.execute(&keepers[keeper], &task_id);
+            }
+        }
+        client.execute_batch(&keepers[2], &vec![&env, broken]);
+
+        let top = client.top_keepers(&2);
+        assert_eq!(top.len(), 2);
+        assert_eq!(top.get(0).unwrap().keeper, keepers[1]);
+        assert_eq!(top.get(0).unwrap().stats.executions, 3);
+        assert_eq!(top.get(1).unwrap().keeper, keepers[0]);
+
+        assert_eq!(client.top_keepers(&10).get(2).unwrap().keeper, keepers[2]);
+        assert!(client.top_keepers(&0).is_empty());
+    }
+
+    // ── Accrued rewards ──────────────────────────────────────────────────────
+
+    #[test]
+    fn test_accrued_rewards_across_many_executions() {
+        let (env, id) = setup();
+        let client = SoroTaskContractClient::new(&env, &id);
+        let keeper = Address::generate(&env);
+        let token = client.get_token();
+
+        client.set_payout_mode(&keeper, &PayoutMode::Accrue);
+        assert_eq!(env.auths()[0].0, keeper);
+        assert_eq!(client.get_payout_mode(&keeper), PayoutMode::Accrue);
+
+        register_many(&env, &client, &[3_600; 3]);
+        for run in 1..=4u64 {
+            set_timestamp(&env, run * 3_600);
+            client.execute(&keeper, &1);
+            client.execute_batch(&keeper, &vec![&env, 2, 3]);
+        }
+
+        // 12 executions at a fee of 10, none transferred yet.
+        assert_eq!(balance(&env, &token, &keeper), 0);
+        assert_eq!(client.get_accrued_rewards(&keeper, &token), 120);
+        assert_eq!(balance(&env, &token, &id), 3 * 1_000);
+
+        assert_eq!(client.claim_rewards(&keeper, &token), 120);
+        assert_eq!(env.auths()[0].0, keeper);
+        assert_eq!(last_event_name(&env), Symbol::new(&env, "RewardsClaimed"));
+        assert_eq!(balance(&env, &token, &keeper), 120);
+        assert_eq!(balance(&env, &token, &id), 3 * 1_000 - 120);
+        assert_eq!(client.get_accrued_rewards(&keeper, &token), 0);
+
+        assert_eq!(
+            client.try_claim_rewards(&keeper, &token),
+            Err(Ok(Error::NothingToClaim.into()))
+        );
+    }
+
+    /// Switching back to transfers pays directly and keeps the ledger.
+    #[test]
+    fn test_payout_mode_switch_keeps_accrued_rewards() {
+        let (env, id) = setup();
+        let client = SoroTaskContractClient::new(&env, &id);
+        let keeper = Address::generate(&env);
+        let token = client.get_token();
+
+        let cfg = base_config(&env, env.register_contract(None, MockTarget));
+        let task_id = register_funded(&env, &client, &cfg);
+
+        client.set_payout_mode(&keeper, &PayoutMode::Accrue);
+        set_timestamp(&env, 3_600);
+        client.execute(&keeper, &task_id);
+
+        client.set_payout_mode(&keeper, &PayoutMode::Transfer);
+        set_timestamp(&env, 7_200);
+        client.execute(&keeper, &task_id);
+
+        assert_eq!(balance(&env, &token, &keeper), 10);
+        assert_eq!(client.get_accrued_rewards(&keeper, &token), 10);
+        client.claim_rewards(&keeper, &token);
+        assert_eq!(balance(&env, &token, &keeper), 20);
+    }
+
+    // ── Fee policies ─────────────────────────────────────────────────────────
+
+    /// The base fee is charged plus a per-argument amount.
+    #[test]
+    fn test_per_arg_fee_policy() {
+        let (env, id) = setup();
+        let client = SoroTaskContractClient::new(&env, &id);
+        let keeper = Address::generate(&env);
+
+        let cfg = TaskParams {
+            function: Symbol::new(&env, "add"),
+            args: vec![&env, 2i64.into_val(&env), 3i64.into_val(&env)],
+            fee_policy: FeePolicy::PerArg(4),
+            ..base_config(&env, env.register_contract(None, MockTarget))
+        };
+        let task_id = register_funded(&env, &client, &cfg);
+
+        set_timestamp(&env, 3_600);
+        client.execute(&keeper, &task_id);
+        assert_eq!(balance(&env, &client.get_token(), &keeper), 18);
+        assert_eq!(client.get_task(&task_id).unwrap().gas_balance, 982);
+    }
+
+    /// A per-argument fee that overflows is rejected instead of wrapping.
+    #[test]
+    fn test_per_arg_fee_overflow() {
+        let (env, id) = setup();
+        let client = SoroTaskContractClient::new(&env, &id);
+        let keeper = Address::generate(&env);
+
+        let cfg = TaskParams {
+            function: Symbol::new(&env, "add"),
+            args: vec![&env, 2i64.into_val(&env), 3i64.into_val(&env)],
+            gas_balance: 100,
+            fee: 0,
+            fee_policy: FeePolicy::PerArg(i128::MAX - 499),
+            ..base_config(&env, env.register_contract(None, MockTarget))
+        };
+        let task_id = register_funded(&env, &client, &cfg);
+
+        set_timestamp(&env, 3_600);
+        assert_eq!(
+            client.try_execute(&keeper, &task_id),
+            Err(Ok(Error::InvalidAmount.into()))
+        );
+        assert_eq!(client.get_task(&task_id).unwrap().gas_balance, 100);
+    }
+
+    /// Per-argument pricing counts the arguments a payload resolver supplies.
+    #[test]
+    fn test_per_arg_fee_counts_payload_args() {
+        let (env, id) = setup();
+        let client = SoroTaskContractClient::new(&env, &id);
+        let keeper = Address::generate(&env);
+
+        // The oracle's quote supplies two arguments; the task has none.
+        let target = env.register_contract(None, MockTarget);
+        let cfg = TaskParams {
+            args: Vec::new(&env),
+            resolver_mode: ResolverMode::Payload,
+            fee: 0,
+            fee_policy: FeePolicy::PerArg(5),
+            ..oracle_config(&env, target, "quote", vec![&env, 7_i64.into_val(&env)])
+        };
+        let task_id = register_funded(&env, &client, &cfg);
+
+        set_timestamp(&env, 3_600);
+        let result = client.execute_batch(&keeper, &vec![&env, task_id]);
+        assert_eq!(result.total_reward, 10);
+    }
+
+    #[test]
+    fn test_quoted_fee_policy() {
+        let (env, id) = setup();
+        let client = SoroTaskContractClient::new(&env, &id);
+        let keeper = Address::generate(&env);
+        let token = client.get_token();
+
+        let cfg = TaskParams {
+            fee_policy: FeePolicy::Quoted(50),
+            ..base_config(&env, env.register_contract(None, MockTarget))
+        };
+        let task_id = register_funded(&env, &client, &cfg);
+
+        set_timestamp(&env, 3_600);
+        assert_eq!(
+            client.try_execute_quoted(&keeper, &task_id, &51),
+            Err(Ok(Error::FeeQuoteTooHigh.into()))
+        );
+        assert_eq!(
+            client.try_execute_quoted(&keeper, &task_id, &-1),
+            Err(Ok(Error::InvalidAmount.into()))
+        );
+        assert_eq!(
+            client.execute_quoted(&keeper, &task_id, &35),
+            ExecutionOutcome::Executed
+        );
+        assert_eq!(env.auths()[0].0, keeper);
+        assert_eq!(balance(&env, &token, &keeper), 35);
+
+        // Without a quote the task's fee applies.
+        set_timestamp(&env, 7_200);
+        client.execute(&keeper, &task_id);
+        assert_eq!(balance(&env, &token, &keeper), 45);
+        assert_eq!(client.get_task(&task_id).unwrap().gas_balance, 955);
+    }
+
+    /// A quote the balance cannot cover is rejected.
+    #[test]
+    fn test_quoted_fee_exceeding_balance() {
+        let (env, id) = setup();
+        let client = SoroTaskContractClient::new(&env, &id);
+        let keeper = Address::generate(&env);
+
+        let cfg = TaskParams {
+            gas_balance: 20,
+            fee_policy: FeePolicy::Quoted(50),
+            ..base_config(&env, env.register_contract(None, MockTarget))
+        };
+        let task_id = register_funded(&env, &client, &cfg);
+
+        set_timestamp(&env, 3_600);
+        assert_eq!(
+            client.try_execute_quoted(&keeper, &task_id, &30),
+            Err(Ok(Error::InsufficientBalance.into()))
+        );
+    }
+
+    /// A quote the balance covers is accepted even when the task's fee is not.
+    #[test]
+    fn test_quoted_fee_below_task_fee() {
+        let (env, id) = setup();
+        let client = SoroTaskContractClient::new(&env, &id);
+        let keeper = Address::generate(&env);
+
+        let cfg = TaskParams {
+            gas_balance: 20,
+            fee: 50,
+            fee_policy: FeePolicy::Quoted(50),
+            ..base_config(&env, env.register_contract(None, MockTarget))
+        };
+        let task_id = register_funded(&env, &client, &cfg);
+
+        set_timestamp(&env, 3_600);
+        assert_eq!(
+            client.try_execute(&keeper, &task_id),
+            Err(Ok(Error::InsufficientBalance.into()))
+        );
+        assert_eq!(
+            client.execute_quoted(&keeper, &task_id, &15),
+            ExecutionOutcome::Executed
+        );
+        assert_eq!(client.get_task(&task_id).unwrap().gas_balance, 5);
+    }
+
+    #[test]
+    fn test_invalid_fee_policy() {
+        let (env, id) = setup();
+        let client = SoroTaskContractClient::new(&env, &id);
+
+        for fee_policy in [FeePolicy::PerArg(-1), FeePolicy::Quoted(5)] {
+            let cfg = TaskParams {
+                fee_policy,
+                ..base_config(&env, Address::generate(&env))
+            };
+            assert_eq!(
+                client.try_register(&cfg),
+                Err(Ok(Error::InvalidAmount.into()))
+            );
+        }
+    }
+
+    #[test]
+    fn test_set_fee_policy() {
+        let (env, id) = setup();
+        let client = SoroTaskContractClient::new(&env, &id);
+
+        let cfg = base_config(&env, Address::generate(&env));
+        let task_id = register_funded(&env, &client, &cfg);
 
-        let stored = client.get_task(&task_id).expect("task should exist");
-        assert_eq!(stored.target, target);
-        assert_eq!(stored.interval, 3_600);
-        assert_eq!(stored.last_run, 0, "last_run must start at 0");
+        client.set_fee_policy(&task_id, &20, &FeePolicy::Quoted(100));
+        assert_eq!(env.auths()[0].0, cfg.creator);
+        assert_eq!(last_event_name(&env), Symbol::new(&env, "TaskUpdated"));
+        let stored = client.get_task(&task_id).unwrap();
+        assert_eq!(stored.fee, 20);
+        assert_eq!(stored.fee_policy, FeePolicy::Quoted(100));
+
+        assert_eq!(
+            client.try_set_fee_policy(&task_id, &200, &FeePolicy::Quoted(100)),
+            Err(Ok(Error::InvalidAmount.into()))
+        );
     }
 
-    /// Querying a task id that was never registered returns None.
+    // ── Protected addresses ──────────────────────────────────────────────────
+
+    /// A task calling the fee token (or the contract itself) could move the
+    /// escrow with the contract's own authority.
     #[test]
-    fn test_get_task_missing_returns_none() {
+    fn test_register_rejects_protected_target() {
         let (env, id) = setup();
         let client = SoroTaskContractClient::new(&env, &id);
-        assert!(client.get_task(&99_u64).is_none());
+        let attacker = Address::generate(&env);
+
+        let theft = TaskParams {
+            function: Symbol::new(&env, "transfer"),
+            args: vec![
+                &env,
+                id.into_val(&env),
+                attacker.into_val(&env),
+                1_000_i128.into_val(&env),
+            ],
+            gas_balance: 0,
+            ..base_config(&env, client.get_token())
+        };
+        assert_eq!(
+            client.try_register(&theft),
+            Err(Ok(Error::ProtectedAddress.into()))
+        );
+        let reentrant = TaskParams {
+            gas_balance: 0,
+            ..base_config(&env, id.clone())
+        };
+        assert_eq!(
+            client.try_register(&reentrant),
+            Err(Ok(Error::ProtectedAddress.into()))
+        );
     }
 
-    /// A successful cross-contract call updates last_run to the ledger timestamp.
     #[test]
-    fn test_execute_invokes_target_and_updates_last_run() {
+    fn test_update_task_rejects_protected_target() {
         let (env, id) = setup();
         let client = SoroTaskContractClient::new(&env, &id);
 
-        let target = env.register_contract(None, MockTarget);
-        let task_id = client.register(&base_config(&env, target));
+        let cfg = base_config(&env, env.register_contract(None, MockTarget));
+        let task_id = register_funded(&env, &client, &cfg);
+        let patch = TaskPatch {
+            target: Some(client.get_token()),
+            ..empty_patch(&env)
+        };
+        assert_eq!(
+            client.try_update_task(&task_id, &patch),
+            Err(Ok(Error::ProtectedAddress.into()))
+        );
+    }
 
-        set_timestamp(&env, 12_345);
-        client.execute(&task_id);
+    /// A resolver call runs with the same authority as a target call.
+    #[test]
+    fn test_rejects_protected_resolver() {
+        let (env, id) = setup();
+        let client = SoroTaskContractClient::new(&env, &id);
+        let attacker = Address::generate(&env);
 
-        let updated = client.get_task(&task_id).unwrap();
+        let theft = TaskParams {
+            resolver: Some(client.get_token()),
+            resolver_function: Some(Symbol::new(&env, "transfer")),
+            resolver_args: vec![
+                &env,
+                id.into_val(&env),
+                attacker.into_val(&env),
+                1_000_i128.into_val(&env),
+            ],
+            gas_balance: 0,
+            ..base_config(&env, env.register_contract(None, MockTarget))
+        };
         assert_eq!(
-            updated.last_run, 12_345,
-            "last_run must reflect ledger timestamp after execution"
+            client.try_register(&theft),
+            Err(Ok(Error::ProtectedAddress.into()))
+        );
+
+        let cfg = base_config(&env, env.register_contract(None, MockTarget));
+        let task_id = register_funded(&env, &client, &cfg);
+        let patch = TaskPatch {
+            resolver: Some(id.clone()),
+            ..empty_patch(&env)
+        };
+        assert_eq!(
+            client.try_update_task(&task_id, &patch),
+            Err(Ok(Error::ProtectedAddress.into()))
         );
     }
 
-    /// Args stored in TaskConfig are forwarded correctly to the target function.
     #[test]
-    fn test_execute_forwards_args_to_target() {
+    fn test_rejects_protected_condition_clause() {
         let (env, id) = setup();
         let client = SoroTaskContractClient::new(&env, &id);
 
-        let target = env.register_contract(None, MockTarget);
+        let cfg = TaskParams {
+            conditions: vec![
+                &env,
+                ResolverClause {
+                    resolver: client.get_token(),
+                    function: Symbol::new(&env, "transfer"),
+                    args: Vec::new(&env),
+                },
+            ],
+            condition_tree: vec![&env, ConditionNode::Clause(0)],
+            gas_balance: 0,
+            ..base_config(&env, env.register_contract(None, MockTarget))
+        };
+        assert_eq!(
+            client.try_register(&cfg),
+            Err(Ok(Error::ProtectedAddress.into()))
+        );
+    }
 
-        let mut args: Vec<Val> = Vec::new(&env);
-        args.push_back(5_i64.into_val(&env));
-        args.push_back(3_i64.into_val(&env));
+    /// Shared or orphaned nodes would let a small tree fan out into an
+    /// exponential number of clause calls.
+    #[test]
+    fn test_condition_nodes_need_exactly_one_parent() {
+        let (env, id) = setup();
+        let client = SoroTaskContractClient::new(&env, &id);
+        let clauses = env.register_contract(None, clauses::MockClauses);
 
-        let cfg = TaskConfig {
-            creator: Address::generate(&env),
-            target,
-            function: Symbol::new(&env, "add"),
-            args,
-            resolver: None,
-            interval: 60,
-            last_run: 0,
-            gas_balance: 500,
+        let with_tree = |condition_tree: Vec<ConditionNode>| TaskParams {
+            conditions: vec![&env, clause(&env, &clauses, "no")],
+            condition_tree,
+            ..base_config(&env, Address::generate(&env))
         };
+        let shared = with_tree(vec![
+            &env,
+            ConditionNode::Or(vec![&env, 1, 2]),
+            ConditionNode::Or(vec![&env, 2, 3]),
+            ConditionNode::Clause(0),
+            ConditionNode::Clause(0),
+        ]);
+        let duplicate = with_tree(vec![
+            &env,
+            ConditionNode::And(vec![&env, 1, 1]),
+            ConditionNode::Clause(0),
+        ]);
+        let orphan = with_tree(vec![
+            &env,
+            ConditionNode::Not(1),
+            ConditionNode::Clause(0),
+            ConditionNode::Clause(0),
+        ]);
+        for cfg in [shared, duplicate, orphan] {
+            assert_eq!(
+                client.try_register(&cfg),
+                Err(Ok(Error::InvalidCondition.into()))
+            );
+        }
 
-        let task_id = client.register(&cfg);
-        set_timestamp(&env, 99_999);
-        client.execute(&task_id);
-
-        assert_eq!(client.get_task(&task_id).unwrap().last_run, 99_999);
+        let tree = with_tree(vec![
+            &env,
+            ConditionNode::Or(vec![&env, 1, 2]),
+            ConditionNode::Clause(0),
+            ConditionNode::Not(3),
+            ConditionNode::Clause(0),
+        ]);
+        mint(&env, &client.get_token(), &tree.creator, tree.gas_balance);
+        client.register(&tree);
     }
 
-    /// When a resolver returns true the target is invoked and last_run updated.
+    /// A keeper slashed to zero re-registers without a second registry entry.
     #[test]
-    fn test_execute_with_resolver_true_proceeds() {
+    fn test_reregister_slashed_keeper_keeps_single_entry() {
         let (env, id) = setup();
         let client = SoroTaskContractClient::new(&env, &id);
 
-        let target = env.register_contract(None, MockTarget);
-        let resolver = env.register_contract(None, resolver_true::MockResolverTrue);
+        client.set_keeper_config(&KeeperConfig {
+            slash_bps: BPS_DENOMINATOR,
+            ..client.get_keeper_config()
+        });
+        let cfg = base_config(&env, env.register_contract(None, MockTarget));
+        let (task_id, keeper) = claimed_task(&env, &client, &cfg);
+        set_timestamp(&env, 7_200);
+        client.report_missed(&Address::generate(&env), &task_id);
+        assert_eq!(client.get_keeper(&keeper).unwrap().stake, 0);
 
-        let cfg = TaskConfig {
-            resolver: Some(resolver),
-            ..base_config(&env, target)
-        };
+        mint(&env, &client.get_token(), &keeper, 500);
+        client.register_keeper(&keeper, &500);
+        assert_eq!(client.active_keepers(), vec![&env, keeper.clone()]);
+        assert_eq!(client.get_keeper(&keeper).unwrap().stake, 500);
+    }
 
-        let task_id = client.register(&cfg);
-        set_timestamp(&env, 55_000);
-        client.execute(&task_id);
+    #[test]
+    fn test_keeper_registry_is_capped() {
+        let (env, id) = setup();
+        let client = SoroTaskContractClient::new(&env, &id);
 
+        for _ in 0..MAX_ACTIVE_KEEPERS {
+            bonded_keeper(&env, &client, 1);
+        }
+        let late = Address::generate(&env);
+        mint(&env, &client.get_token(), &late, 1);
         assert_eq!(
-            client.get_task(&task_id).unwrap().last_run,
-            55_000,
-            "resolver approved — last_run must be updated"
+            client.try_register_keeper(&late, &1),
+            Err(Ok(Error::KeeperRegistryFull.into()))
         );
+
+        // Existing keepers can still top up.
+        let first = client.active_keepers().get(0).unwrap();
+        mint(&env, &client.get_token(), &first, 1);
+        client.register_keeper(&first, &1);
     }
 
-    /// When a resolver returns false the target is NOT invoked and last_run is
-    /// left unchanged.
+    /// Raising `min_stake` frees the places held by keepers left below it.
     #[test]
-    fn test_execute_with_resolver_false_skips_invocation() {
+    fn test_full_registry_drops_underbonded_keepers() {
         let (env, id) = setup();
         let client = SoroTaskContractClient::new(&env, &id);
 
-        let target = env.register_contract(None, MockTarget);
-        let resolver = env.register_contract(None, resolver_false::MockResolverFalse);
+        for _ in 0..MAX_ACTIVE_KEEPERS {
+            bonded_keeper(&env, &client, 1);
+        }
 
-        let cfg = TaskConfig {
-            resolver: Some(resolver),
-            ..base_config(&env, target)
-        };
+        client.set_keeper_config(&KeeperConfig {
+            min_stake: 5_000,
+            ..client.get_keeper_config()
+        });
+        let honest = Address::generate(&env);
+        mint(&env, &client.get_token(), &honest, 5_000);
+        client.register_keeper(&honest, &5_000);
+        assert_eq!(client.active_keepers(), vec![&env, honest]);
+    }
 
-        let task_id = client.register(&cfg);
-        set_timestamp(&env, 77_777);
-        client.execute(&task_id);
+    #[test]
+    fn test_keeper_config_requires_positive_min_stake() {
+        let (env, id) = setup();
+        let client = SoroTaskContractClient::new(&env, &id);
 
         assert_eq!(
-            client.get_task(&task_id).unwrap().last_run,
-            0,
-            "resolver denied — last_run must not change"
+            client.try_set_keeper_config(&KeeperConfig {
+                min_stake: 0,
+                ..client.get_keeper_config()
+            }),
+            Err(Ok(Error::InvalidAmount.into()))
         );
     }
 
-    /// Calling execute multiple times updates last_run on every successful run.
+    /// Changing what the keeper signed up for drops the assignment, so the
+    /// creator cannot swap in a failing target and collect a slash.
     #[test]
-    fn test_execute_repeated_calls_update_timestamp_each_time() {
+    fn test_creator_changes_unassign_keeper() {
         let (env, id) = setup();
         let client = SoroTaskContractClient::new(&env, &id);
+        let reporter = Address::generate(&env);
 
-        let target = env.register_contract(None, MockTarget);
-        let mut cfg = base_config(&env, target);
-        cfg.interval = 1; // Small interval to allow repeated execution
-        let task_id = client.register(&cfg);
+        let failing = TaskPatch {
+            function: Some(Symbol::new(&env, "fail")),
+            ..empty_patch(&env)
+        };
+        let changes: [&dyn Fn(u64); 3] = [
+            &|task_id| client.update_task(&task_id, &failing),
+            &|task_id| client.set_schedule(&task_id, &Schedule::Interval(60)),
+            &|task_id| client.set_fee_policy(&task_id, &10, &FeePolicy::Fixed),
+        ];
+        for change in changes {
+            let cfg = base_config(&env, env.register_contract(None, MockTarget));
+            let (task_id, _) = claimed_task(&env, &client, &cfg);
+            change(task_id);
+            assert_eq!(client.get_task(&task_id).unwrap().assigned_keeper, None);
 
-        set_timestamp(&env, 1_000);
-        client.execute(&task_id);
-        assert_eq!(client.get_task(&task_id).unwrap().last_run, 1_000);
+            set_timestamp(&env, env.ledger().timestamp() + 100_000);
+            assert_eq!(
+                client.try_report_missed(&reporter, &task_id),
+                Err(Ok(Error::TaskNotAssigned.into()))
+            );
+        }
+    }
 
-        set_timestamp(&env, 2_000);
-        client.execute(&task_id);
+    /// Pausing and deposits restart the grace period instead of counting
+    /// time the keeper could not have run the task.
+    #[test]
+    fn test_grace_period_restarts_after_pause_and_deposit() {
+        let (env, id) = setup();
+        let client = SoroTaskContractClient::new(&env, &id);
+        let reporter = Address::generate(&env);
+
+        let cfg = base_config(&env, env.register_contract(None, MockTarget));
+        let (task_id, _) = claimed_task(&env, &client, &cfg);
+
+        client.pause_task(&task_id);
+        set_timestamp(&env, 50_000);
+        client.resume_task(&task_id);
         assert_eq!(
-            client.get_task(&task_id).unwrap().last_run,
-            2_000,
-            "last_run must advance on each execution"
+            client.try_report_missed(&reporter, &task_id),
+            Err(Ok(Error::NotMissed.into()))
         );
+
+        set_timestamp(&env, 60_000);
+        mint(&env, &client.get_token(), &cfg.creator, 10);
+        client.deposit_gas(&task_id, &10);
+        set_timestamp(&env, 60_000 + 3_599);
+        assert_eq!(
+            client.try_report_missed(&reporter, &task_id),
+            Err(Ok(Error::NotMissed.into()))
+        );
+        set_timestamp(&env, 60_000 + 3_600);
+        assert_eq!(client.report_missed(&reporter, &task_id), 100);
     }
 
+    /// A keeper that is not allowed to run the task cannot have missed it.
     #[test]
-    fn test_register_and_get() {
-        let env = Env::default();
-        env.mock_all_auths();
+    fn test_report_missed_requires_allowed_keeper() {
+        let (env, id) = setup();
+        let client = SoroTaskContractClient::new(&env, &id);
 
-        let contract_id = env.register_contract(None, SoroTaskContract);
-        let client = SoroTaskContractClient::new(&env, &contract_id);
+        let cfg = base_config(&env, env.register_contract(None, MockTarget));
+        let (task_id, keeper) = claimed_task(&env, &client, &cfg);
+        // Simulate a stale assignment that predates the allowlist.
+        env.as_contract(&id, || {
+            let mut config = load_task(&env, task_id);
+            config.allowed_keepers = vec![&env, Address::generate(&env)];
+            env.storage()
+                .persistent()
+                .set(&DataKey::Task(task_id), &config);
+        });
 
-        let creator = Address::generate(&env);
-        let target = Address::generate(&env);
+        set_timestamp(&env, 50_000);
+        assert_eq!(
+            client.try_report_missed(&Address::generate(&env), &task_id),
+            Err(Ok(Error::NotMissed.into()))
+        );
+        assert_eq!(client.get_keeper(&keeper).unwrap().stake, 1_000);
+    }
 
-        let config = TaskConfig {
-            creator: creator.clone(),
-            target: target.clone(),
-            function: Symbol::new(&env, "hello"),
-            args: vec![&env, 0i128.into_val(&env)],
-            resolver: None,
-            interval: 3600,
-            last_run: 0,
-            gas_balance: 1000,
+    /// A claimer whose run fails in the target is not slashed for it; the
+    /// grace period restarts from the attempt.
+    #[test]
+    fn test_report_missed_after_target_failure() {
+        let (env, id) = setup();
+        let client = SoroTaskContractClient::new(&env, &id);
+        let reporter = Address::generate(&env);
+
+        let cfg = TaskParams {
+            function: Symbol::new(&env, "fail"),
+            ..base_config(&env, env.register_contract(None, MockTarget))
         };
+        let (task_id, keeper) = claimed_task(&env, &client, &cfg);
 
-        let task_id = client.register(&config);
-        assert_eq!(task_id, 1);
+        set_timestamp(&env, 7_000);
+        let result = client.execute_batch(&keeper, &vec![&env, task_id]);
+        assert_eq!(
+            result.outcomes.get(0).unwrap(),
+            ExecutionOutcome::TargetFailed
+        );
+        assert_eq!(client.get_task(&task_id).unwrap().assigned_at, 7_000);
 
-        let retrieved_config = client.get_task(&task_id).unwrap();
-        assert_eq!(retrieved_config.creator, config.creator);
-        assert_eq!(retrieved_config.target, config.target);
-        assert_eq!(retrieved_config.function, config.function);
-        assert_eq!(retrieved_config.interval, config.interval);
-        assert_eq!(retrieved_config.gas_balance, config.gas_balance);
+        set_timestamp(&env, 7_200);
+        assert_eq!(
+            client.try_report_missed(&reporter, &task_id),
+            Err(Ok(Error::NotMissed.into()))
+        );
+        set_timestamp(&env, 7_000 + 3_600);
+        assert_eq!(client.report_missed(&reporter, &task_id), 100);
+    }
 
-        // Check event
-        let events = env.events().all();
-        let last_event = events.last().unwrap();
+    /// A claimer left under `min_stake` cannot run a `bonded_only` task, so it
+    /// cannot have missed it.
+    #[test]
+    fn test_report_missed_requires_bonded_keeper_for_bonded_only_task() {
+        let (env, id) = setup();
+        let client = SoroTaskContractClient::new(&env, &id);
 
-        assert_eq!(last_event.0, contract_id);
+        let cfg = TaskParams {
+            bonded_only: true,
+            ..base_config(&env, env.register_contract(None, MockTarget))
+        };
+        let (task_id, keeper) = claimed_task(&env, &client, &cfg);
+        client.set_keeper_config(&KeeperConfig {
+            min_stake: 5_000,
+            ..client.get_keeper_config()
+        });
 
-        let topics = last_event.1.clone();
+        set_timestamp(&env, 50_000);
         assert_eq!(
-            Symbol::from_val(&env, &topics.get(0).unwrap()),
-            Symbol::new(&env, "TaskRegistered")
+            client.try_execute(&keeper, &task_id),
+            Err(Ok(Error::KeeperNotBonded.into()))
         );
-        assert_eq!(u64::from_val(&env, &topics.get(1).unwrap()), 1u64);
-
-        let data: Address = last_event.2.clone().into_val(&env);
-        assert_eq!(data, creator);
+        assert_eq!(
+            client.try_report_missed(&Address::generate(&env), &task_id),
+            Err(Ok(Error::NotMissed.into()))
+        );
+        assert_eq!(client.get_keeper(&keeper).unwrap().stake, 1_000);
     }
 
+    /// Locking the claimer out through the allowlist drops its claim, so the
+    /// creator cannot slash it for runs it may not perform.
     #[test]
-    fn test_sequential_ids() {
-        let env = Env::default();
-        env.mock_all_auths();
-
-        let contract_id = env.register_contract(None, SoroTaskContract);
-        let client = SoroTaskContractClient::new(&env, &contract_id);
+    fn test_allowlist_change_drops_disallowed_claim() {
+        let (env, id) = setup();
+        let client = SoroTaskContractClient::new(&env, &id);
 
-        let creator = Address::generate(&env);
-        let target = Address::generate(&env);
+        let cfg = base_config(&env, env.register_contract(None, MockTarget));
+        let (task_id, keeper) = claimed_task(&env, &client, &cfg);
 
-        let config = TaskConfig {
-            creator: creator.clone(),
-            target: target.clone(),
-            function: Symbol::new(&env, "hello"),
-            args: vec![&env],
-            resolver: None,
-            interval: 3600,
-            last_run: 0,
-            gas_balance: 1000,
-        };
+        client.add_allowed_keeper(&task_id, &keeper);
+        assert_eq!(
+            client.get_task(&task_id).unwrap().assigned_keeper,
+            Some(keeper.clone())
+        );
 
-        let id1 = client.register(&config);
-        let id2 = client.register(&config);
+        client.add_allowed_keeper(&task_id, &cfg.creator);
+        client.remove_allowed_keeper(&task_id, &keeper);
+        assert_eq!(client.get_task(&task_id).unwrap().assigned_keeper, None);
 
-        assert_eq!(id1, 1);
-        assert_eq!(id2, 2);
+        set_timestamp(&env, 50_000);
+        assert_eq!(
+            client.try_report_missed(&cfg.creator, &task_id),
+            Err(Ok(Error::TaskNotAssigned.into()))
+        );
+        assert_eq!(client.get_keeper(&keeper).unwrap().stake, 1_000);
     }
 
     #[test]
-    fn test_register_invalid_interval() {
-        let env = Env::default();
-        env.mock_all_auths();
+    fn test_adding_other_keeper_drops_unlisted_claim() {
+        let (env, id) = setup();
+        let client = SoroTaskContractClient::new(&env, &id);
 
-        let contract_id = env.register_contract(None, SoroTaskContract);
-        let client = SoroTaskContractClient::new(&env, &contract_id);
+        let cfg = base_config(&env, env.register_contract(None, MockTarget));
+        let (task_id, _) = claimed_task(&env, &client, &cfg);
 
-        let creator = Address::generate(&env);
-        let target = Address::generate(&env);
+        client.add_allowed_keeper(&task_id, &cfg.creator);
+        assert_eq!(client.get_task(&task_id).unwrap().assigned_keeper, None);
+    }
 
-        let config = TaskConfig {
-            creator: creator.clone(),
-            target: target.clone(),
-            function: Symbol::new(&env, "hello"),
-            args: vec![&env],
-            resolver: None,
-            interval: 0, // Invalid
-            last_run: 0,
-            gas_balance: 1000,
-        };
+    /// Keepers below the minimum stake are left out of the rotation.
+    #[test]
+    fn test_rotation_skips_underbonded_keepers() {
+        let (env, id) = setup();
+        let client = SoroTaskContractClient::new(&env, &id);
 
-        let result = client.try_register(&config);
-        assert_eq!(result, Err(Ok(soroban_sdk::Error::from_contract_error(1))));
+        let cfg = base_config(&env, env.register_contract(None, MockTarget));
+        let task_id = register_funded(&env, &client, &cfg);
+        let small = bonded_keeper(&env, &client, 100);
+        let large = bonded_keeper(&env, &client, 1_000);
+
+        client.set_keeper_config(&KeeperConfig {
+            min_stake: 500,
+            ..client.get_keeper_config()
+        });
+        for run in 1..=6u64 {
+            assert_eq!(client.designated_keeper(&task_id), Some(large.clone()));
+            set_timestamp(&env, run * 3_600);
+            assert_eq!(client.execute(&large, &task_id), ExecutionOutcome::Executed);
+        }
+
+        client.set_keeper_config(&KeeperConfig {
+            min_stake: 5_000,
+            ..client.get_keeper_config()
+        });
+        assert_eq!(client.designated_keeper(&task_id), None);
+        assert_eq!(client.active_keepers(), vec![&env, small, large]);
     }
 
+    /// The leaderboard never grows past its cap; the weakest entry drops out.
     #[test]
-    fn test_execute_honors_interval() {
-        let env = Env::default();
-        env.mock_all_auths();
+    fn test_top_keepers_is_capped() {
+        let (env, id) = setup();
+        let client = SoroTaskContractClient::new(&env, &id);
 
-        let contract_id = env.register_contract(None, SoroTaskContract);
-        let client = SoroTaskContractClient::new(&env, &contract_id);
+        let count = MAX_RANKED_KEEPERS as u64 + 1;
+        register_many(&env, &client, &[3_600; 1]);
+        set_timestamp(&env, 3_600);
+        let veteran = Address::generate(&env);
+        client.execute(&veteran, &1);
 
-        let creator = Address::generate(&env);
-        let dummy_id = env.register_contract(None, DummyContract);
-        let target = dummy_id.clone();
+        // Every newcomer fails one task and runs another, which ranks below
+        // the veteran and ties with the newcomers already on the board.
+        let mut last = veteran.clone();
+        let target = env.register_contract(None, MockTarget);
+        for _ in 0..count {
+            let broken = TaskParams {
+                function: Symbol::new(&env, "fail"),
+                ..base_config(&env, target.clone())
+            };
+            let broken = register_funded(&env, &client, &broken);
+            let ok = register_funded(&env, &client, &base_config(&env, target.clone()));
+            let keeper = Address::generate(&env);
+            client.execute_batch(&keeper, &vec![&env, broken, ok]);
+            last = keeper;
+        }
 
-        let config = TaskConfig {
-            creator: creator.clone(),
-            target: target.clone(),
-            function: Symbol::new(&env, "hello"),
-            args: Vec::new(&env),
-            resolver: None,
-            interval: 100,
-            last_run: 0,
-            gas_balance: 1000,
-        };
+        let top = client.top_keepers(&u32::MAX);
+        assert_eq!(top.len(), MAX_RANKED_KEEPERS);
+        assert_eq!(top.get(0).unwrap().keeper, veteran);
+        assert!(!top.iter().any(|entry| entry.keeper == last));
+        assert_eq!(client.get_keeper_stats(&last).failures, 1);
+    }
 
-        let task_id = client.register(&config);
+    /// A keeper left off the full board is not promoted when a listed keeper
+    /// falls below it; it takes its place once it records again.
+    #[test]
+    fn test_top_keepers_is_approximate() {
+        let (env, id) = setup();
+        let client = SoroTaskContractClient::new(&env, &id);
 
-        // First execution (ledger 50, last_run 0, interval 100)
-        // 50 < 0 + 100 -> returns early
-        env.ledger().set_timestamp(50);
-        client.execute(&task_id);
-        assert_eq!(client.get_task(&task_id).unwrap().last_run, 0);
+        let target = env.register_contract(None, MockTarget);
+        let broken = TaskParams {
+            function: Symbol::new(&env, "fail"),
+            ..base_config(&env, target.clone())
+        };
+        let broken = register_funded(&env, &client, &broken);
+        let mut keepers = Vec::<Address>::new(&env);
+        set_timestamp(&env, 3_600);
+        for _ in 0..=MAX_RANKED_KEEPERS {
+            let task_id = register_funded(&env, &client, &base_config(&env, target.clone()));
+            let keeper = Address::generate(&env);
+            client.execute(&keeper, &task_id);
+            keepers.push_back(keeper);
+        }
+        let outsider = keepers.last().unwrap();
+        let ranked = |keeper: &Address| {
+            client
+                .top_keepers(&u32::MAX)
+                .iter()
+                .any(|entry| entry.keeper == *keeper)
+        };
+        assert!(!ranked(&outsider));
 
-        env.ledger().set_timestamp(150);
-        client.execute(&task_id);
-        assert_eq!(client.get_task(&task_id).unwrap().last_run, 150);
+        // The first keeper now ranks below the outsider, which stays off.
+        let first = keepers.first().unwrap();
+        client.execute_batch(&first, &vec![&env, broken]);
+        assert!(ranked(&first));
+        assert!(!ranked(&outsider));
 
-        // Next execution too soon
-        env.ledger().set_timestamp(200);
-        client.execute(&task_id);
-        assert_eq!(client.get_task(&task_id).unwrap().last_run, 150);
+        let task_id = register_funded(&env, &client, &base_config(&env, target));
+        client.execute(&outsider, &task_id);
+        assert_eq!(client.top_keepers(&1).get(0).unwrap().keeper, outsider);
+        assert!(!ranked(&first));
     }
 }